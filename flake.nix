@@ -14,7 +14,7 @@
       pname = "shome";
       version = "1.0.0";
       src = ./.;
-      cargoSha256 = "sha256-ItP2wDuz9XmJ3BUp+vZuqIU62OtVTBtG67ZqbaFLx+w=";
+      cargoLock.lockFile = ./Cargo.lock;
     };
 
     defaultPackage.x86_64-linux = self.packages.x86_64-linux.shome;
@@ -60,7 +60,7 @@ pub async fn debug(client: &Client) -> Result<(), Error> {
         debug_info
             .hardware_nodes
             .into_iter()
-            .map(|n| TableHardwareNode::from(n))
+            .map(TableHardwareNode::from)
             .collect::<Vec<TableHardwareNode>>(),
     );
     table.with(Style::modern().remove_horizontal()).with(
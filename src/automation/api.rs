@@ -0,0 +1,140 @@
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use smarthome_sdk_rs::{Client, Error, Result};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TimingMode {
+    Normal,
+    Sunrise,
+    Sunset,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct Automation {
+    pub id: u32,
+    pub name: String,
+    pub description: String,
+    pub cron_expression: String,
+    #[serde(default)]
+    pub cron_description: String,
+    pub homescript_id: String,
+    pub owner: String,
+    pub enabled: bool,
+    pub timing_mode: TimingMode,
+}
+
+/// The data which is required in order to create or modify an automation
+#[derive(Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct AutomationRequest {
+    pub name: String,
+    pub description: String,
+    pub hour: u8,
+    pub minute: u8,
+    /// The days on which the automation runs, `0` is Sunday
+    pub days: Vec<u8>,
+    pub homescript_id: String,
+    pub enabled: bool,
+    pub timing_mode: TimingMode,
+}
+
+#[derive(Serialize)]
+struct ModifyAutomationRequest<'request> {
+    id: u32,
+    data: &'request AutomationRequest,
+}
+
+#[derive(Serialize)]
+struct DeleteAutomationRequest {
+    id: u32,
+}
+
+impl Automation {
+    /// Converts the automation back into a request so that it can be modified
+    /// Returns `None` if the cron expression uses a format which the server never generates
+    pub fn to_request(&self) -> Option<AutomationRequest> {
+        // The server uses the format `<minute> <hour> * * <days>`
+        let mut fields = self.cron_expression.split_whitespace();
+        let minute = fields.next()?.parse().ok()?;
+        let hour = fields.next()?.parse().ok()?;
+        let days = match fields.nth(2)? {
+            "*" => (0..7).collect(),
+            days => days
+                .split(',')
+                .map(|day| day.parse().ok())
+                .collect::<Option<Vec<u8>>>()?,
+        };
+        Some(AutomationRequest {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            hour,
+            minute,
+            days,
+            homescript_id: self.homescript_id.clone(),
+            enabled: self.enabled,
+            timing_mode: self.timing_mode,
+        })
+    }
+}
+
+/// Returns the automations of the current user
+pub async fn list_personal(client: &Client) -> Result<Vec<Automation>> {
+    let response = client
+        .client
+        .execute(client.build_request::<()>(Method::GET, "/api/automation/list/personal", None)?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(response.json::<Vec<Automation>>().await?),
+        status => Err(Error::Smarthome(status)),
+    }
+}
+
+/// Creates a new automation for the current user
+pub async fn create(client: &Client, data: &AutomationRequest) -> Result<()> {
+    let response = client
+        .client
+        .execute(client.build_request::<&AutomationRequest>(
+            Method::POST,
+            "/api/automation/add",
+            Some(data),
+        )?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        status => Err(Error::Smarthome(status)),
+    }
+}
+
+/// Replaces the data of an existing automation
+pub async fn modify(client: &Client, id: u32, data: &AutomationRequest) -> Result<()> {
+    let response = client
+        .client
+        .execute(client.build_request::<ModifyAutomationRequest>(
+            Method::PUT,
+            "/api/automation/modify",
+            Some(ModifyAutomationRequest { id, data }),
+        )?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        status => Err(Error::Smarthome(status)),
+    }
+}
+
+/// Deletes an automation of the current user
+pub async fn delete(client: &Client, id: u32) -> Result<()> {
+    let response = client
+        .client
+        .execute(client.build_request::<DeleteAutomationRequest>(
+            Method::DELETE,
+            "/api/automation/delete",
+            Some(DeleteAutomationRequest { id }),
+        )?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        status => Err(Error::Smarthome(status)),
+    }
+}
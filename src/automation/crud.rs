@@ -0,0 +1,91 @@
+use log::{debug, info};
+use reqwest::StatusCode;
+use smarthome_sdk_rs::{Client, Error as SdkError};
+
+use super::{
+    api::{self, AutomationRequest},
+    errors::{Error, Result},
+};
+
+pub async fn create_automation(client: &Client, data: AutomationRequest) -> Result<()> {
+    if data.name.is_empty() || data.name.len() > 30 {
+        return Err(Error::InvalidData(
+            "name must not be empty and shall not exceed 30 characters".to_string(),
+        ));
+    }
+    // Validate that the target script exists before creating the automation
+    if !client
+        .list_personal_homescripts()
+        .await?
+        .iter()
+        .any(|script| script.data.id == data.homescript_id)
+    {
+        return Err(Error::ScriptDoesNotExist(data.homescript_id));
+    }
+
+    debug!("Creating automation `{}`...", data.name);
+    api::create(client, &data).await.map_err(map_sdk_error)?;
+    info!("Successfully created automation `{}`", data.name);
+    Ok(())
+}
+
+pub async fn set_enabled(client: &Client, ids: &[u32], enabled: bool) -> Result<()> {
+    let automations = match api::list_personal(client).await {
+        Ok(response) => response,
+        Err(err) => return Err(Error::FetchAutomations(err)),
+    };
+    for id in ids {
+        let Some(automation) = automations.iter().find(|automation| automation.id == *id) else {
+            return Err(Error::AutomationDoesNotExist(*id));
+        };
+        if automation.enabled == enabled {
+            info!(
+                "Automation `{id}` is already {}",
+                if enabled { "enabled" } else { "disabled" }
+            );
+            continue;
+        }
+        let Some(data) = automation.to_request() else {
+            return Err(Error::UnsupportedCronExpression {
+                id: *id,
+                expression: automation.cron_expression.clone(),
+            });
+        };
+        debug!(
+            "{}abling automation `{id}`...",
+            if enabled { "En" } else { "Dis" }
+        );
+        api::modify(client, *id, &AutomationRequest { enabled, ..data })
+            .await
+            .map_err(map_sdk_error)?;
+        info!(
+            "Successfully {}abled automation `{id}`",
+            if enabled { "en" } else { "dis" }
+        );
+    }
+    Ok(())
+}
+
+pub async fn delete_automation(client: &Client, id: u32) -> Result<()> {
+    debug!("Deleting automation `{id}`...");
+    match api::delete(client, id).await {
+        Ok(_) => {
+            info!("Successfully deleted automation `{id}`");
+            Ok(())
+        }
+        Err(SdkError::Smarthome(StatusCode::UNPROCESSABLE_ENTITY)) => {
+            Err(Error::AutomationDoesNotExist(id))
+        }
+        Err(err) => Err(map_sdk_error(err)),
+    }
+}
+
+fn map_sdk_error(err: SdkError) -> Error {
+    match err {
+        SdkError::Smarthome(StatusCode::FORBIDDEN) => Error::PermissionDenied,
+        SdkError::Smarthome(StatusCode::UNPROCESSABLE_ENTITY) => {
+            Error::InvalidData("the server rejected the automation data".to_string())
+        }
+        _ => Error::Smarthome(err),
+    }
+}
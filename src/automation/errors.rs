@@ -0,0 +1,40 @@
+use smarthome_sdk_rs::Error as SdkError;
+use std::fmt::Display;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub enum Error {
+    FetchAutomations(SdkError),
+    AutomationDoesNotExist(u32),
+    ScriptDoesNotExist(String),
+    UnsupportedCronExpression { id: u32, expression: String },
+    InvalidData(String),
+    PermissionDenied,
+    Smarthome(SdkError),
+}
+
+impl From<SdkError> for Error {
+    fn from(err: SdkError) -> Self {
+        Self::Smarthome(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::FetchAutomations(err) => format!("Could not fetch automations: {err}"),
+                Self::AutomationDoesNotExist(id) =>
+                    format!("Automation `{id}` does not exist or is inaccessible"),
+                Self::ScriptDoesNotExist(id) =>
+                    format!("Script `{id}` does not exist or is inaccessible"),
+                Self::UnsupportedCronExpression { id, expression } => format!("Cannot modify automation `{id}`: its cron expression `{expression}` is not supported by this CLI"),
+                Self::InvalidData(message) => format!("Invalid data: {message}"),
+                Self::PermissionDenied => "You are lacking permission to manage automations".to_string(),
+                Self::Smarthome(err) => format!("Smarthome Error: {err}"),
+            }
+        )
+    }
+}
@@ -0,0 +1,120 @@
+use smarthome_sdk_rs::Client;
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    api::{self, Automation, TimingMode},
+    errors::{Error, Result},
+};
+
+#[derive(Tabled)]
+pub struct TableAutomation {
+    #[tabled(rename = "ID")]
+    pub id: u32,
+    #[tabled(rename = "Name")]
+    pub name: String,
+    #[tabled(rename = "Script")]
+    pub homescript_id: String,
+    #[tabled(rename = "Schedule")]
+    pub schedule: String,
+    #[tabled(display_with("Self::display_timing_mode"), rename = "Timing")]
+    pub timing_mode: TimingMode,
+    #[tabled(display_with("Self::display_enabled"), rename = "Status")]
+    pub enabled: bool,
+}
+
+impl From<Automation> for TableAutomation {
+    fn from(source: Automation) -> Self {
+        Self {
+            id: source.id,
+            name: source.name,
+            homescript_id: source.homescript_id,
+            schedule: display_schedule(&source.cron_expression, &source.cron_description),
+            timing_mode: source.timing_mode,
+            enabled: source.enabled,
+        }
+    }
+}
+
+impl TableAutomation {
+    fn display_timing_mode(timing_mode: &TimingMode) -> String {
+        match timing_mode {
+            TimingMode::Normal => "normal",
+            TimingMode::Sunrise => "sunrise",
+            TimingMode::Sunset => "sunset",
+        }
+        .to_string()
+    }
+
+    fn display_enabled(enabled: &bool) -> String {
+        {
+            if *enabled {
+                "\x1b[1;32mENABLED\x1b[1;0m"
+            } else {
+                "\x1b[1;31mDISABLED\x1b[1;0m"
+            }
+        }
+        .to_string()
+    }
+}
+
+fn display_schedule(cron_expression: &str, cron_description: &str) -> String {
+    match cron_description.is_empty() {
+        true => cron_expression.to_string(),
+        false => cron_description.to_string(),
+    }
+}
+
+pub async fn list_personal(client: &Client, script_id: Option<&str>) -> Result<()> {
+    let automations = match api::list_personal(client).await {
+        Ok(response) => response
+            .into_iter()
+            .filter(|automation| match script_id {
+                Some(id) => automation.homescript_id == id,
+                None => true,
+            })
+            .map(TableAutomation::from),
+        Err(err) => return Err(Error::FetchAutomations(err)),
+    };
+    let mut table = Table::new(automations);
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+    Ok(())
+}
+
+pub async fn show(client: &Client, id: u32) -> Result<()> {
+    let automation = match api::list_personal(client).await {
+        Ok(response) => response.into_iter().find(|automation| automation.id == id),
+        Err(err) => return Err(Error::FetchAutomations(err)),
+    };
+    let Some(automation) = automation else {
+        return Err(Error::AutomationDoesNotExist(id));
+    };
+
+    println!(
+        "\x1b[1;32m=== Automation {} ===\x1b[0m
+  Name         {}
+  Description  {}
+  Owner        {}
+  Script       {}
+  Schedule     {} (`{}`)
+  Timing       {}
+  Status       {}",
+        automation.id,
+        automation.name,
+        automation.description,
+        automation.owner,
+        automation.homescript_id,
+        display_schedule(&automation.cron_expression, &automation.cron_description),
+        automation.cron_expression,
+        TableAutomation::display_timing_mode(&automation.timing_mode),
+        TableAutomation::display_enabled(&automation.enabled),
+    );
+    Ok(())
+}
@@ -0,0 +1,67 @@
+use smarthome_sdk_rs::Client;
+
+use crate::cli::{AutomationCommand, AutomationTiming};
+use api::{AutomationRequest, TimingMode};
+use errors::{Error, Result};
+
+pub mod api;
+mod crud;
+mod errors;
+mod listing;
+
+pub async fn handle_subcommand(command: AutomationCommand, client: &Client) -> Result<()> {
+    match command {
+        AutomationCommand::Ls { script } => listing::list_personal(client, script.as_deref()).await,
+        AutomationCommand::Show { id } => listing::show(client, id).await,
+        AutomationCommand::New {
+            name,
+            script,
+            at,
+            days,
+            timing,
+            description,
+            disabled,
+        } => {
+            // The time is only optional if it is determined by the sun
+            let at = match (at, timing) {
+                (Some(at), _) => (at.hour, at.minute),
+                (None, AutomationTiming::Sunrise | AutomationTiming::Sunset) => (0, 0),
+                (None, AutomationTiming::Normal) => {
+                    return Err(Error::InvalidData(
+                        "a time (`--at HH:MM`) is required when using normal timing".to_string(),
+                    ))
+                }
+            };
+            crud::create_automation(
+                client,
+                AutomationRequest {
+                    name,
+                    description: description
+                        .unwrap_or_else(|| "Created through the CLI".to_string()),
+                    hour: at.0,
+                    minute: at.1,
+                    days: match days.is_empty() {
+                        true => (0..7).collect(),
+                        false => days.into_iter().map(|day| day as u8).collect(),
+                    },
+                    homescript_id: script,
+                    enabled: !disabled,
+                    timing_mode: match timing {
+                        AutomationTiming::Normal => TimingMode::Normal,
+                        AutomationTiming::Sunrise => TimingMode::Sunrise,
+                        AutomationTiming::Sunset => TimingMode::Sunset,
+                    },
+                },
+            )
+            .await
+        }
+        AutomationCommand::Enable { ids } => crud::set_enabled(client, &ids, true).await,
+        AutomationCommand::Disable { ids } => crud::set_enabled(client, &ids, false).await,
+        AutomationCommand::Del { ids } => {
+            for id in ids {
+                crud::delete_automation(client, id).await?
+            }
+            Ok(())
+        }
+    }
+}
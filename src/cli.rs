@@ -1,7 +1,8 @@
-use std::str::FromStr;
+use std::{net::SocketAddr, str::FromStr, time::Duration};
 
 use anyhow::bail;
-use clap::{Parser, Subcommand};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, NaiveTime, TimeZone};
+use clap::{ArgGroup, Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
 #[clap(author, version, about)]
@@ -27,24 +28,76 @@ pub struct Args {
     pub subcommand: Command,
 }
 
-#[derive(Subcommand, PartialEq, Eq)]
+#[derive(Subcommand, PartialEq)]
 pub enum Command {
     /// Power subcommands
     #[clap(subcommand)]
     Power(PowerCommand),
 
+    /// Device subcommands
+    #[clap(subcommand)]
+    Device(DeviceCommand),
+
     /// Homescript subcommands
     #[clap(subcommand)]
     Hms(HmsCommand),
 
+    /// Automation subcommands
+    #[clap(subcommand)]
+    Automation(AutomationCommand),
+
+    /// Schedule subcommands
+    #[clap(subcommand)]
+    Schedule(ScheduleCommand),
+
     /// Admin subcommands
     #[clap(subcommand)]
     Admin(AdminCommand),
 
+    /// Serves power and hardware metrics in the Prometheus format
+    ServeMetrics {
+        /// The address on which the metrics are served
+        #[clap(short, long, value_parser, default_value = "127.0.0.1:9520")]
+        listen: SocketAddr,
+        /// How often the server is polled (for example `30s` or `1m`)
+        #[clap(short, long, value_parser, default_value = "30s")]
+        interval: DurationArg,
+    },
+
     /// Displays the file path of the CLI's configuration file
     Config,
 }
 
+#[derive(Subcommand, PartialEq)]
+pub enum DeviceCommand {
+    /// Lists devices and their capabilities
+    #[clap(visible_alias = "list")]
+    Ls {
+        /// Lists all devices which are present on the Smarthome-server
+        #[clap(short, long, value_parser)]
+        all: bool,
+        /// Only lists devices which have all of the given capabilities
+        #[clap(short, long, value_enum)]
+        capability: Vec<Capability>,
+    },
+    /// Shows the capabilities, configuration, driver and power information of a device
+    Show {
+        /// The ID of the device
+        id: String,
+    },
+    /// Sets a dimmable value of a device, for example its brightness
+    Dim {
+        /// The ID of the device
+        id: String,
+        /// The new value, which must be in the range of the dimmable
+        #[clap(allow_negative_numbers = true)]
+        value: f64,
+        /// The label of the dimmable, only required if the device has more than one
+        #[clap(short, long, value_parser)]
+        label: Option<String>,
+    },
+}
+
 #[derive(Subcommand, PartialEq, Eq)]
 pub enum PowerCommand {
     /// Shows the user's personal switches
@@ -52,30 +105,208 @@ pub enum PowerCommand {
         #[clap(short, long, value_parser)]
         /// Shows all switches which are present on the Smarthome-server
         all: bool,
+        /// Groups the switches by their room and shows per-room totals
+        #[clap(short, long, value_parser)]
+        by_room: bool,
+        /// Only shows switches in the given room(s)
+        #[clap(short, long, value_parser)]
+        room: Vec<String>,
     },
     /// Displays power current power draw and a historic summary
     Draw {
         #[clap(short, long, value_parser)]
         /// Hides the table and only shows the most relevant information
         simple: bool,
+        #[clap(flatten)]
+        timespan: TimespanArgs,
+        /// Additionally shows a table which aggregates the power draw per hour or per day
+        #[clap(short, long, value_enum)]
+        per: Option<Resolution>,
+        /// Additionally renders the power draw history as a chart
+        #[clap(short, long, value_parser)]
+        chart: bool,
+    },
+    /// Exports the power draw history to a file or stdout
+    Export {
+        /// The format of the exported data
+        #[clap(short, long, value_enum, default_value_t = ExportFormat::Csv)]
+        format: ExportFormat,
+        /// The file to which the data is written, stdout if omitted
+        #[clap(short, long, value_parser)]
+        output: Option<String>,
+        #[clap(flatten)]
+        timespan: TimespanArgs,
+    },
+    /// Estimates the energy used by each device from locally recorded device states
+    Energy {
+        #[clap(flatten)]
+        timespan: TimespanArgs,
     },
     /// Toggles the power state of a switch
     Toggle {
-        /// A list of switch-ids to toggle (individually)
-        #[clap(required = true)]
-        switch_ids: Vec<String>,
+        #[clap(flatten)]
+        selector: SwitchSelector,
+        /// Switches all selected switches to a common state: on if any of them is off, otherwise off
+        #[clap(long, value_parser)]
+        group: bool,
+        /// How many switches may be switched at the same time (overrides the config)
+        #[clap(short, long, value_parser)]
+        jobs: Option<usize>,
+        /// Re-reads the power states after switching and reports switches which did not reach their target state
+        #[clap(long, value_parser)]
+        verify: bool,
     },
     /// Activates a switch
     On {
-        /// A list of switch-ids to activate
-        #[clap(required = true)]
-        switch_ids: Vec<String>,
+        #[clap(flatten)]
+        selector: SwitchSelector,
+        #[clap(flatten)]
+        timer: TimerArgs,
+        /// How many switches may be switched at the same time (overrides the config)
+        #[clap(short, long, value_parser)]
+        jobs: Option<usize>,
+        /// Re-reads the power states after switching and reports switches which did not reach their target state
+        #[clap(long, value_parser)]
+        verify: bool,
     },
     /// Deactivates a switch
     Off {
-        /// A list of switch-id,s to deactivate
-        #[clap(required = true)]
+        #[clap(flatten)]
+        selector: SwitchSelector,
+        #[clap(flatten)]
+        timer: TimerArgs,
+        /// How many switches may be switched at the same time (overrides the config)
+        #[clap(short, long, value_parser)]
+        jobs: Option<usize>,
+        /// Re-reads the power states after switching and reports switches which did not reach their target state
+        #[clap(long, value_parser)]
+        verify: bool,
+    },
+    /// Waits until a switch reaches the given power state, fails if the timeout elapses first
+    Wait {
+        /// The switch-id or name of the switch
+        switch_id: String,
+        /// The power state to wait for
+        #[clap(short, long, value_enum)]
+        state: PowerState,
+        /// How long to wait at most (for example `60s` or `5m`)
+        #[clap(short, long, value_parser, default_value = "60s")]
+        timeout: DurationArg,
+        /// How often the power state is polled
+        #[clap(short, long, value_parser, default_value = "1s")]
+        interval: DurationArg,
+    },
+    /// Shows a live-updating power dashboard which allows toggling switches
+    Top {
+        /// How often the data should be refreshed (for example `5s` or `1m`)
+        #[clap(short, long, value_parser, default_value = "5s")]
+        interval: DurationArg,
+    },
+    /// Reverts the most recent power change
+    Undo {
+        /// How many switches may be switched at the same time (overrides the config)
+        #[clap(short, long, value_parser)]
+        jobs: Option<usize>,
+    },
+    /// Lists recent power changes
+    History {
+        /// How many power changes should be displayed
+        #[clap(short, long, value_parser, default_value_t = 10)]
+        limit: usize,
+    },
+    /// Scene subcommands
+    #[clap(subcommand)]
+    Scene(SceneCommand),
+}
+
+/// Selects the timespan which is analyzed, the last 24 hours if no argument is given
+#[derive(clap::Args, PartialEq, Eq)]
+pub struct TimespanArgs {
+    /// Includes the given timespan before `--until` or now (for example `7d`)
+    #[clap(long, value_parser, conflicts_with = "since")]
+    pub range: Option<DurationArg>,
+    /// Only includes data recorded after this point in time (`YYYY-MM-DD [HH:MM]`)
+    #[clap(long, value_parser)]
+    pub since: Option<DateTimeArg>,
+    /// Only includes data recorded before this point in time (`YYYY-MM-DD [HH:MM]`)
+    #[clap(long, value_parser)]
+    pub until: Option<DateTimeArg>,
+}
+
+#[derive(clap::Args, PartialEq, Eq)]
+pub struct TimerArgs {
+    /// Switches after the given delay instead of immediately (for example `10m`)
+    #[clap(long = "in", value_parser, group = "timer")]
+    pub delay: Option<DurationArg>,
+    /// Restores the previous power states after the given duration (for example `30m`)
+    #[clap(long = "for", value_parser, group = "timer")]
+    pub duration: Option<DurationArg>,
+    /// Uses a local timer in the foreground instead of a server-side schedule
+    #[clap(long, value_parser, requires = "timer")]
+    pub local: bool,
+}
+
+#[derive(clap::Args, PartialEq, Eq, Default)]
+pub struct SwitchSelector {
+    /// A list of switch-ids or switch names to select (pick interactively if omitted)
+    pub switch_ids: Vec<String>,
+    /// Selects all of the user's switches
+    #[clap(short, long, value_parser)]
+    pub all: bool,
+    /// Selects all switches in the given room(s)
+    #[clap(short, long, value_parser)]
+    pub room: Vec<String>,
+    /// Selects all switches whose ID or name matches a glob pattern (for example `lamp-*`)
+    #[clap(short = 'g', long, value_parser)]
+    pub name_glob: Option<String>,
+    /// Excludes the given switch-ids or switch names from the selection
+    #[clap(short, long, value_delimiter = ',')]
+    pub exclude: Vec<String>,
+    /// Only selects switches which are currently turned on
+    #[clap(
+        long,
+        value_parser,
+        visible_alias = "if-on",
+        conflicts_with = "only_off"
+    )]
+    pub only_on: bool,
+    /// Only selects switches which are currently turned off
+    #[clap(long, value_parser, visible_alias = "if-off")]
+    pub only_off: bool,
+}
+
+#[derive(Subcommand, PartialEq, Eq)]
+pub enum SceneCommand {
+    /// Displays a list of all scenes
+    #[clap(visible_alias = "list")]
+    Ls,
+    /// Switches all devices of a scene whose state differs from the scene
+    Apply {
+        /// The name of the scene
+        name: String,
+        /// How many switches may be switched at the same time (overrides the config)
+        #[clap(short, long, value_parser)]
+        jobs: Option<usize>,
+    },
+    /// Saves the current power states of the user's switches as a scene
+    Save {
+        /// The name of the scene
+        name: String,
+        /// Only includes the given switch-ids in the scene
         switch_ids: Vec<String>,
+        /// Overwrites an existing scene with the same name
+        #[clap(short, long, value_parser)]
+        force: bool,
+    },
+    /// Shows which devices differ from a scene
+    Diff {
+        /// The name of the scene
+        name: String,
+    },
+    /// Deletes a scene
+    Del {
+        /// The name of the scene
+        name: String,
     },
 }
 
@@ -151,6 +382,49 @@ pub enum HmsScriptCommand {
         #[clap(required = true)]
         ids: Vec<String>,
     },
+    /// Rename a script and / or move it into another workspace
+    Mv {
+        /// The current ID of the script
+        old_id: String,
+        /// The new ID of the script, the ID is kept if omitted
+        #[clap(required_unless_present = "workspace")]
+        new_id: Option<String>,
+        /// The workspace which the script should be moved to
+        #[clap(short, long, value_parser)]
+        workspace: Option<String>,
+    },
+    /// Shows which scripts and automations depend on a script and what it depends on
+    Deps {
+        /// The ID of the script
+        id: String,
+        /// Prints the dependency graph in the Graphviz DOT format
+        #[clap(long, value_parser)]
+        dot: bool,
+    },
+    /// Copy scripts from one server profile to another
+    Promote {
+        /// The ID(s) of the script(s) to be promoted
+        #[clap(required_unless_present = "workspace", conflicts_with = "workspace")]
+        ids: Vec<String>,
+        /// Promotes every script of this workspace
+        #[clap(short, long, value_parser)]
+        workspace: Option<String>,
+        /// The server profile to copy from, defaults to the selected server
+        #[clap(long, value_parser)]
+        from: Option<String>,
+        /// The server profile to copy to
+        #[clap(long, value_parser)]
+        to: String,
+        /// Only shows the changes without modifying the target server
+        #[clap(short, long, value_parser)]
+        dry_run: bool,
+        /// Will promote the scripts even if lint errors were found on the target
+        #[clap(short, long, value_parser)]
+        force: bool,
+        /// Skips the confirmation prompt
+        #[clap(short, long, value_parser)]
+        yes: bool,
+    },
     /// Push local changes to the server
     Push {
         #[clap(short, long, value_parser)]
@@ -169,6 +443,256 @@ pub enum HmsScriptCommand {
     },
 }
 
+#[derive(Subcommand, PartialEq, Eq)]
+pub enum AutomationCommand {
+    /// Displays a list of personal automations
+    #[clap(visible_alias = "list")]
+    Ls {
+        /// Only shows automations which execute the given script
+        #[clap(short, long, value_parser)]
+        script: Option<String>,
+    },
+    /// Shows details about an automation
+    Show {
+        /// The ID of the automation
+        id: u32,
+    },
+    /// Creates a new automation
+    #[clap(visible_alias = "create")]
+    New {
+        /// A friendly name for the new automation
+        name: String,
+        /// The ID of the script to be executed by the automation
+        #[clap(short, long, value_parser)]
+        script: String,
+        /// The time of day (`HH:MM`) at which the script is executed
+        #[clap(short, long, value_parser)]
+        at: Option<TimeOfDay>,
+        /// The days on which the automation runs, runs every day if omitted
+        #[clap(short, long, value_delimiter = ',')]
+        days: Vec<Weekday>,
+        /// Whether the time is determined by the sunrise or sunset
+        #[clap(short, long, value_enum, default_value_t = AutomationTiming::Normal)]
+        timing: AutomationTiming,
+        /// A description for the new automation
+        #[clap(long, value_parser)]
+        description: Option<String>,
+        /// Creates the automation in a disabled state
+        #[clap(long, value_parser)]
+        disabled: bool,
+    },
+    /// Enables automations
+    Enable {
+        /// The ID(s) of the automation(s) to be enabled
+        #[clap(required = true)]
+        ids: Vec<u32>,
+    },
+    /// Disables automations
+    Disable {
+        /// The ID(s) of the automation(s) to be disabled
+        #[clap(required = true)]
+        ids: Vec<u32>,
+    },
+    /// Deletes automations
+    #[clap(visible_alias = "delete")]
+    Del {
+        /// The ID(s) of the automation(s) to be deleted
+        #[clap(required = true)]
+        ids: Vec<u32>,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum AutomationTiming {
+    /// Runs at the specified time
+    Normal,
+    /// Runs at sunrise
+    Sunrise,
+    /// Runs at sunset
+    Sunset,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum Weekday {
+    Sun,
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+}
+
+#[derive(Subcommand, PartialEq, Eq)]
+pub enum ScheduleCommand {
+    /// Displays a list of pending schedules
+    #[clap(visible_alias = "list")]
+    Ls,
+    /// Creates a new schedule which runs once at the given time
+    #[clap(visible_alias = "create")]
+    #[clap(group(ArgGroup::new("target").required(true).args(["script", "code", "switch"])))]
+    New {
+        /// A friendly name for the new schedule
+        name: String,
+        /// The time of day (`HH:MM`) at which the schedule is executed
+        #[clap(short, long, value_parser)]
+        at: TimeOfDay,
+        /// The ID of a script to be executed
+        #[clap(short, long, value_parser)]
+        script: Option<String>,
+        /// Homescript code to be executed
+        #[clap(long, value_parser)]
+        code: Option<String>,
+        /// Power states to be set, for example `lamp:on,fan:off`
+        #[clap(long, value_delimiter = ',')]
+        switch: Vec<SwitchJobArg>,
+    },
+    /// Deletes pending schedules
+    #[clap(visible_alias = "delete")]
+    Del {
+        /// The ID(s) of the schedule(s) to be deleted
+        #[clap(required = true)]
+        ids: Vec<u32>,
+    },
+}
+
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct TimeOfDay {
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl FromStr for TimeOfDay {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((hour, minute)) = s.split_once(':') else {
+            bail!("Time must use the format `HH:MM`")
+        };
+        let (hour, minute): (u8, u8) = (hour.parse()?, minute.parse()?);
+        if hour > 23 || minute > 59 {
+            bail!("Time `{s}` is out of range")
+        }
+        Ok(Self { hour, minute })
+    }
+}
+
+/// A duration consisting of a number and a unit (`ms`, `s`, `m`, `h` or `d`), for example `30s`
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct DurationArg(pub Duration);
+
+/// Longer durations are rejected so that adding them to the current time cannot overflow
+const MAX_DURATION: Duration = Duration::from_secs(366 * 24 * 60 * 60);
+
+impl FromStr for DurationArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+        let (value, unit) = s.split_at(split);
+        if value.is_empty() {
+            bail!("Duration must start with a number, for example `30s`")
+        }
+        let value: u64 = value.parse()?;
+        let millis_per_unit: u64 = match unit {
+            "ms" => 1,
+            "s" | "" => 1000,
+            "m" => 60 * 1000,
+            "h" => 60 * 60 * 1000,
+            "d" => 24 * 60 * 60 * 1000,
+            _ => bail!("Unknown duration unit `{unit}`: expected `ms`, `s`, `m`, `h` or `d`"),
+        };
+        let duration = match value.checked_mul(millis_per_unit) {
+            Some(millis) => Duration::from_millis(millis),
+            None => bail!("Duration `{s}` is too long: it must not exceed 366 days"),
+        };
+        if duration.is_zero() {
+            bail!("Duration must not be zero")
+        }
+        if duration > MAX_DURATION {
+            bail!("Duration `{s}` is too long: it must not exceed 366 days")
+        }
+        Ok(Self(duration))
+    }
+}
+
+/// A point in local time, either `YYYY-MM-DD` (midnight) or `YYYY-MM-DD HH:MM`
+#[derive(PartialEq, Eq, Clone, Copy)]
+pub struct DateTimeArg(pub DateTime<Local>);
+
+impl FromStr for DateTimeArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let naive = match NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M")
+            .or_else(|_| NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M"))
+        {
+            Ok(naive) => naive,
+            Err(_) => match NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+                Ok(date) => date.and_time(NaiveTime::MIN),
+                Err(_) => bail!("Time must use the format `YYYY-MM-DD` or `YYYY-MM-DD HH:MM`"),
+            },
+        };
+        match Local.from_local_datetime(&naive).earliest() {
+            Some(time) => Ok(Self(time)),
+            None => bail!("Time `{s}` does not exist in the local timezone"),
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Csv,
+    Json,
+    #[value(name = "openmetrics")]
+    OpenMetrics,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum PowerState {
+    On,
+    Off,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum Capability {
+    Base,
+    Power,
+    Dimmable,
+    Sensor,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Hour,
+    Day,
+}
+
+#[derive(PartialEq, Eq, Clone)]
+pub struct SwitchJobArg {
+    pub switch_id: String,
+    pub power_on: bool,
+}
+
+impl FromStr for SwitchJobArg {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((switch_id, state)) = s.rsplit_once(':') else {
+            bail!("Switch job must use the format `<switch-id>:<on|off>`")
+        };
+        let power_on = match state {
+            "on" => true,
+            "off" => false,
+            _ => bail!("Invalid power state `{state}`: expected `on` or `off`"),
+        };
+        Ok(Self {
+            switch_id: switch_id.to_string(),
+            power_on,
+        })
+    }
+}
+
 #[derive(Subcommand, PartialEq, Eq)]
 pub enum AdminCommand {
     // Shows debug information
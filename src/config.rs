@@ -1,13 +1,16 @@
 use std::{
+    collections::BTreeMap,
     env,
     fmt::Display,
     fs::{self, File},
     io::{self, Write},
     path::Path,
+    process::Command,
 };
 
-use log::debug;
+use log::{debug, warn};
 use serde::{Deserialize, Serialize};
+use smarthome_sdk_rs::{Auth, Client, User};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
@@ -15,7 +18,27 @@ pub type Result<T> = std::result::Result<T, Error>;
 pub enum Error {
     IO(io::Error),
     Parse(toml::de::Error),
+    Edit(toml_edit::TomlError),
     Validate(ValidateError),
+    // A secret could not be read from its source, holds the ID of the server and the reason
+    Secret { server_id: String, message: String },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::IO(err) => write!(f, "IO error: {err}"),
+            Self::Parse(err) => write!(f, "invalid TOML syntax: {err}"),
+            Self::Edit(err) => write!(f, "invalid TOML syntax: {err}"),
+            Self::Validate(err) => write!(f, "Validation failed: {err}"),
+            Self::Secret { server_id, message } => {
+                write!(
+                    f,
+                    "Could not read credentials of server `{server_id}`: {message}"
+                )
+            }
+        }
+    }
 }
 
 impl From<io::Error> for Error {
@@ -30,6 +53,12 @@ impl From<toml::de::Error> for Error {
     }
 }
 
+impl From<toml_edit::TomlError> for Error {
+    fn from(e: toml_edit::TomlError) -> Self {
+        Self::Edit(e)
+    }
+}
+
 impl From<ValidateError> for Error {
     fn from(err: ValidateError) -> Self {
         Self::Validate(err)
@@ -50,9 +79,19 @@ pub enum ValidateError {
         token: String,
         message: &'static str,
     },
+    // More than one source is specified for the same secret, holds the ID of the server and the secret's name
+    AmbiguousSecret {
+        server_id: String,
+        secret: &'static str,
+    },
     // No Username is present whilst the token is empty, holds the ID of the affected server
     EmptyUserName(String),
     NoServers,
+    // A tariff has an invalid time range or price, holds the tariff's name and the reason
+    InvalidTariff {
+        name: String,
+        message: &'static str,
+    },
 }
 
 impl Display for ValidateError {
@@ -61,8 +100,10 @@ impl Display for ValidateError {
             Self::AmbiguousAuth(id) => format!("Ambiguous authentication at server `{id}`: username or password specified whilst token is not empty"),
             Self::DuplicateID(id) => format!("Duplicate server ID: the ID `{id}` must be unique"),
             Self::InvalidToken { server_id, token, message } => format!("Malformed access token: token `{token}` at server `{server_id}` is invalid: {message}"),
+            Self::AmbiguousSecret { server_id, secret } => format!("Ambiguous {secret} at server `{server_id}`: only one source may be specified for the {secret}"),
             Self::EmptyUserName(id) => format!("No authentication provided for server `{id}`: token and username are both empty"),
             Self::NoServers => "No servers specified: at least one (default) server must be specified to use this CLI".to_string(),
+            Self::InvalidTariff { name, message } => format!("Invalid tariff `{name}`: {message}"),
         })
     }
 }
@@ -71,14 +112,54 @@ impl Display for ValidateError {
 pub struct Config {
     pub homescript: HomescriptConfig,
     pub power: PowerConfig,
+    // Maps scene names to the desired power state of each switch
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub scenes: BTreeMap<String, BTreeMap<String, bool>>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     pub servers: Vec<SmarthomeServer>,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct PowerConfig {
-    pub unit_symbol: char,
+    // The currency in which costs are displayed, for example `€` or `CHF`
+    #[serde(alias = "unit_symbol")]
+    pub currency: String,
+    // The price per kWh which applies whenever no tariff matches
+    pub cost_per_kwh: f64,
+    // A fixed charge which is paid per day, regardless of the usage
+    #[serde(default)]
+    pub standing_charge_per_day: f64,
+    // Time-of-use tariffs, the first tariff which matches a point in time determines its price
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tariffs: Vec<Tariff>,
+    // How many switches may be switched at the same time
+    #[serde(default = "default_power_concurrency")]
+    pub concurrency: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Tariff {
+    pub name: String,
     pub cost_per_kwh: f64,
+    // The tariff applies from the start hour (inclusive) until the end hour (exclusive)
+    // If the end hour is lower than the start hour, the tariff spans midnight
+    pub start_hour: u8,
+    pub end_hour: u8,
+    // The weekdays on which the tariff applies, every day if empty
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub days: Vec<TariffDay>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum TariffDay {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -91,9 +172,21 @@ pub struct HomescriptConfig {
 pub struct SmarthomeServer {
     pub id: String,
     pub url: String,
+    #[serde(default)]
     pub username: String,
+    #[serde(default)]
     pub password: String,
+    // A shell command which prints the password, for example `pass show smarthome`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub password_cmd: Option<String>,
+    #[serde(default)]
     pub token: String,
+    // A shell command which prints the access token
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_cmd: Option<String>,
+    // A file which contains the access token, it must not be accessible by other users
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub token_file: Option<String>,
 }
 
 impl Default for Config {
@@ -101,6 +194,7 @@ impl Default for Config {
         Self {
             homescript: HomescriptConfig::default(),
             power: PowerConfig::default(),
+            scenes: BTreeMap::new(),
             servers: vec![SmarthomeServer::default()],
         }
     }
@@ -118,12 +212,19 @@ impl Default for HomescriptConfig {
 impl Default for PowerConfig {
     fn default() -> Self {
         Self {
-            unit_symbol: '€',
+            currency: "€".to_string(),
             cost_per_kwh: 0.3,
+            standing_charge_per_day: 0.0,
+            tariffs: vec![],
+            concurrency: default_power_concurrency(),
         }
     }
 }
 
+fn default_power_concurrency() -> usize {
+    4
+}
+
 impl Default for SmarthomeServer {
     fn default() -> Self {
         Self {
@@ -131,7 +232,117 @@ impl Default for SmarthomeServer {
             url: "http://smarthome.box".to_string(),
             username: String::new(),
             password: String::new(),
+            password_cmd: None,
             token: "-".repeat(32),
+            token_cmd: None,
+            token_file: None,
+        }
+    }
+}
+
+/// The environment variable which overrides the token of the server selected on the command line
+pub const TOKEN_ENV_VAR: &str = "SMARTHOME_TOKEN";
+
+/// Returns the token from the environment, an empty variable is treated as unset
+fn env_token() -> Option<String> {
+    env::var(TOKEN_ENV_VAR)
+        .ok()
+        .filter(|token| !token.is_empty())
+}
+
+impl SmarthomeServer {
+    /// Resolves the credentials of this server from their configured sources
+    /// The token is read from the environment (if allowed), `token_cmd`, `token_file` or `token`, in this order
+    pub fn auth(&self, use_env: bool) -> Result<Auth> {
+        let env_token = match use_env {
+            true => env_token(),
+            false => None,
+        };
+        let token = match (env_token, &self.token_cmd, &self.token_file) {
+            (Some(token), ..) => {
+                debug!("Using access token from `{TOKEN_ENV_VAR}`");
+                token
+            }
+            (None, Some(command), _) => self.run_secret_cmd(command)?,
+            (None, None, Some(path)) => self.read_token_file(path)?,
+            (None, None, None) => self.token.clone(),
+        };
+        if !token.is_empty() {
+            return Ok(Auth::QueryToken(token));
+        }
+        if self.username.is_empty() {
+            return Err(ValidateError::EmptyUserName(self.id.clone()).into());
+        }
+
+        Ok(Auth::QueryPassword(User {
+            username: self.username.clone(),
+            password: match &self.password_cmd {
+                Some(command) => self.run_secret_cmd(command)?,
+                None => self.password.clone(),
+            },
+        }))
+    }
+
+    /// Creates a Smarthome client which is authenticated using the given credentials
+    pub async fn connect(
+        &self,
+        auth: Auth,
+        version_check: bool,
+    ) -> smarthome_sdk_rs::Result<Client> {
+        Client::new(&self.url, auth, version_check).await
+    }
+
+    fn secret_error(&self, message: String) -> Error {
+        Error::Secret {
+            server_id: self.id.clone(),
+            message,
+        }
+    }
+
+    /// Runs a shell command and returns the first line of its output
+    fn run_secret_cmd(&self, command: &str) -> Result<String> {
+        debug!(
+            "Reading secret of server `{}` using `{command}`...",
+            self.id
+        );
+        let output = Command::new("sh")
+            .args(["-c", command])
+            .output()
+            .map_err(|err| self.secret_error(format!("could not run `{command}`: {err}")))?;
+        if !output.status.success() {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            return Err(self.secret_error(match stderr.trim() {
+                "" => format!("`{command}` failed ({})", output.status),
+                stderr => format!("`{command}` failed ({}): {stderr}", output.status),
+            }));
+        }
+        match String::from_utf8_lossy(&output.stdout).lines().next() {
+            Some(secret) if !secret.trim().is_empty() => Ok(secret.trim().to_string()),
+            _ => Err(self.secret_error(format!("`{command}` did not print anything"))),
+        }
+    }
+
+    /// Reads the token from a file which must only be accessible by its owner
+    fn read_token_file(&self, path: &str) -> Result<String> {
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = fs::metadata(path)
+                .map_err(|err| self.secret_error(format!("could not access `{path}`: {err}")))?
+                .permissions()
+                .mode();
+            if mode & 0o077 != 0 {
+                return Err(self.secret_error(format!(
+                    "the token file `{path}` is accessible by other users (mode {:o})\n => Restrict its permissions using `chmod 600 {path}`",
+                    mode & 0o777
+                )));
+            }
+        }
+        let content = fs::read_to_string(path)
+            .map_err(|err| self.secret_error(format!("could not read `{path}`: {err}")))?;
+        match content.trim() {
+            "" => Err(self.secret_error(format!("the token file `{path}` is empty"))),
+            token => Ok(token.to_string()),
         }
     }
 }
@@ -149,7 +360,20 @@ pub fn file_path() -> Option<String> {
     }
 }
 
-pub fn read_config(file_path: &str) -> Result<Option<Config>> {
+/// Returns the directory in which persistent state (like history files) is stored
+pub fn state_dir() -> Option<String> {
+    match env::var("XDG_STATE_HOME") {
+        Ok(xdg_state) if !xdg_state.is_empty() => Some(format!("{xdg_state}/smarthome-cli-rs")),
+        _ => env::var("HOME")
+            .ok()
+            .filter(|home| !home.is_empty())
+            .map(|home| format!("{home}/.local/state/smarthome-cli-rs")),
+    }
+}
+
+/// Reads the config file or creates it if it does not exist yet
+/// The selected server (the first one if `None`) may receive its token from the environment
+pub fn read_config(file_path: &str, selected_server: Option<&str>) -> Result<Option<Config>> {
     // Either read or create a configuration file based on it's current existence
     let path = Path::new(file_path);
     match &path.exists() {
@@ -157,14 +381,21 @@ pub fn read_config(file_path: &str) -> Result<Option<Config>> {
             // The file exists, it can be read
             debug!("Found existing config file at {file_path}");
             let content = fs::read_to_string(path)?;
-            let config = toml::from_str(&content)?;
+            let config: Config = toml::from_str(&content)?;
+            warn_if_exposed(path, &config);
             // Validate the contents of the config file
-            Ok(Some(validate_config(config)?))
+            Ok(Some(validate_config(config, selected_server)?))
         }
         false => {
             // The file does not exist, therefore create a new one
             fs::create_dir_all(path.parent().unwrap())?;
             let mut file = File::create(path)?;
+            // The file might hold credentials later on
+            #[cfg(unix)]
+            {
+                use std::os::unix::fs::PermissionsExt;
+                file.set_permissions(fs::Permissions::from_mode(0o600))?;
+            }
             file.write_all(include_bytes!("default_config.toml"))?;
             // In case a few new struct fields must be serialized
             /* file.write_all(
@@ -178,27 +409,131 @@ pub fn read_config(file_path: &str) -> Result<Option<Config>> {
     }
 }
 
-fn validate_config(config: Config) -> std::result::Result<Config, ValidateError> {
+/// Warns if the config file stores plaintext credentials while being readable by every user
+#[cfg(unix)]
+fn warn_if_exposed(path: &Path, config: &Config) {
+    use std::os::unix::fs::PermissionsExt;
+    let Ok(metadata) = fs::metadata(path) else {
+        return;
+    };
+    let has_secrets = config
+        .servers
+        .iter()
+        .any(|server| !server.password.is_empty() || !server.token.is_empty());
+    if has_secrets && metadata.permissions().mode() & 0o004 != 0 {
+        warn!(
+            "The config file `{}` contains credentials and is readable by every user\n => Restrict its permissions using `chmod 600 {}` or use `password_cmd`, `token_cmd` or `token_file`",
+            path.display(),
+            path.display()
+        );
+    }
+}
+
+#[cfg(not(unix))]
+fn warn_if_exposed(_path: &Path, _config: &Config) {}
+
+/// Adds, replaces or (if `states` is `None`) removes a scene in the config file
+/// Only the scene's table is edited so that comments and formatting of the rest of the file are preserved
+pub fn write_scene(
+    file_path: &str,
+    name: &str,
+    states: Option<&BTreeMap<String, bool>>,
+) -> Result<()> {
+    let mut document: toml_edit::Document = fs::read_to_string(file_path)?.parse()?;
+    let scenes = document.entry("scenes").or_insert_with(|| {
+        // Only the tables of the individual scenes are written, not an empty `[scenes]` header
+        let mut scenes = toml_edit::Table::new();
+        scenes.set_implicit(true);
+        toml_edit::Item::Table(scenes)
+    });
+
+    match (scenes, states) {
+        (toml_edit::Item::Table(scenes), Some(states)) => {
+            scenes.insert(
+                name,
+                toml_edit::Item::Table(scene_table(states).into_table()),
+            );
+        }
+        (toml_edit::Item::Value(toml_edit::Value::InlineTable(scenes)), Some(states)) => {
+            scenes.insert(name, toml_edit::Value::InlineTable(scene_table(states)));
+        }
+        (scenes, None) => {
+            if let Some(scenes) = scenes.as_table_like_mut() {
+                scenes.remove(name);
+            }
+        }
+        // Cannot happen for a config which has been read successfully before
+        (scenes, Some(states)) => {
+            let mut table = toml_edit::Table::new();
+            table.set_implicit(true);
+            table.insert(
+                name,
+                toml_edit::Item::Table(scene_table(states).into_table()),
+            );
+            *scenes = toml_edit::Item::Table(table);
+        }
+    }
+
+    debug!("Writing config file to {file_path}");
+    fs::write(file_path, document.to_string())?;
+    Ok(())
+}
+
+fn scene_table(states: &BTreeMap<String, bool>) -> toml_edit::InlineTable {
+    states
+        .iter()
+        .map(|(switch_id, power_on)| (switch_id.as_str(), *power_on))
+        .collect()
+}
+
+fn validate_config(
+    config: Config,
+    selected_server: Option<&str>,
+) -> std::result::Result<Config, ValidateError> {
     let mut ids: Vec<&str> = Vec::with_capacity(config.servers.len());
     if config.servers.is_empty() {
         return Err(ValidateError::NoServers);
     }
+    // Only the selected server uses the token from the environment
+    let env_token_server = match env_token() {
+        Some(_) => Some(selected_server.unwrap_or(&config.servers[0].id)),
+        None => None,
+    };
     for server in &config.servers {
         // Validate that every ID is unique
         if ids.contains(&server.id.as_str()) {
             return Err(ValidateError::DuplicateID(server.id.clone()));
         }
         ids.push(&server.id);
-        match server.token.is_empty() {
-            true => {
+        // Validate that every secret has at most one source
+        let token_sources = [
+            !server.token.is_empty(),
+            server.token_cmd.is_some(),
+            server.token_file.is_some(),
+        ];
+        if token_sources.iter().filter(|source| **source).count() > 1 {
+            return Err(ValidateError::AmbiguousSecret {
+                server_id: server.id.clone(),
+                secret: "token",
+            });
+        }
+        if !server.password.is_empty() && server.password_cmd.is_some() {
+            return Err(ValidateError::AmbiguousSecret {
+                server_id: server.id.clone(),
+                secret: "password",
+            });
+        }
+
+        match token_sources.contains(&true) {
+            false => {
                 // Validate that there is some form of authentication
-                if server.username.is_empty() {
+                if server.username.is_empty() && env_token_server != Some(server.id.as_str()) {
                     return Err(ValidateError::EmptyUserName(server.id.clone()));
                 }
             }
-            false => {
-                // Validate that the token is well-formed
-                if server.token.len() != 32 {
+            true => {
+                // Validate that the token is well-formed, tokens from other sources are only known when connecting
+                if !server.token.is_empty() && server.token.len() != 32 {
                     return Err(ValidateError::InvalidToken {
                         token: server.token.clone(),
                         server_id: server.id.clone(),
@@ -214,11 +549,34 @@ fn validate_config(config: Config) -> std::result::Result<Config, ValidateError>
                 }
 
                 // Validate that the authentication mode is unambiguous
-                if !server.username.is_empty() || !server.password.is_empty() {
+                if !server.username.is_empty()
+                    || !server.password.is_empty()
+                    || server.password_cmd.is_some()
+                {
                     return Err(ValidateError::AmbiguousAuth(server.id.clone()));
                 }
             }
         }
     }
+    for tariff in &config.power.tariffs {
+        if tariff.start_hour > 23 || tariff.end_hour > 24 {
+            return Err(ValidateError::InvalidTariff {
+                name: tariff.name.clone(),
+                message: "Hours must be between 0 and 24",
+            });
+        }
+        if tariff.start_hour == tariff.end_hour {
+            return Err(ValidateError::InvalidTariff {
+                name: tariff.name.clone(),
+                message: "Start and end hour must differ",
+            });
+        }
+        if tariff.cost_per_kwh < 0.0 {
+            return Err(ValidateError::InvalidTariff {
+                name: tariff.name.clone(),
+                message: "Cost per kWh must not be negative",
+            });
+        }
+    }
     Ok(config)
 }
@@ -0,0 +1,35 @@
+use reqwest::{Method, StatusCode};
+use serde::Serialize;
+use smarthome_sdk_rs::{Client, Error, Result};
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DimRequest<'a> {
+    device_id: &'a str,
+    dim: DimValue<'a>,
+}
+
+#[derive(Serialize)]
+struct DimValue<'a> {
+    label: &'a str,
+    value: f64,
+}
+
+/// Sets the value of a dimmable of a device, for example its brightness
+pub async fn dim(client: &Client, device_id: &str, label: &str, value: f64) -> Result<()> {
+    let response = client
+        .client
+        .execute(client.build_request::<DimRequest>(
+            Method::POST,
+            "/api/devices/action/dim",
+            Some(DimRequest {
+                device_id,
+                dim: DimValue { label, value },
+            }),
+        )?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        status => Err(Error::Smarthome(status)),
+    }
+}
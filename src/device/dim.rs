@@ -0,0 +1,124 @@
+use log::{debug, info};
+use reqwest::StatusCode;
+use smarthome_sdk_rs::{Client, DeviceCapability, DeviceDimmable, Error as SdkError};
+
+use super::{
+    api,
+    errors::{Error, Result},
+    find_device,
+};
+
+/// Sets a dimmable of a device after validating it against the device's capabilities
+/// The label may be omitted if the device only has a single dimmable
+pub async fn set_dimmable(
+    client: &Client,
+    device_id: &str,
+    label: Option<&str>,
+    value: f64,
+) -> Result<()> {
+    let device = find_device(client, device_id).await?;
+    if !device
+        .extractions
+        .config
+        .capabilities
+        .contains(&DeviceCapability::Dimmable)
+    {
+        return Err(Error::NotDimmable(device_id.to_string()));
+    }
+
+    let dimmables = device.extractions.dimmables.unwrap_or_default();
+    let labels = || dimmables.iter().map(|d| d.label.clone()).collect();
+    let dimmable = match label {
+        Some(label) => dimmables
+            .iter()
+            .find(|dimmable| dimmable.label == label)
+            .ok_or_else(|| Error::UnknownDimmable {
+                device_id: device_id.to_string(),
+                label: label.to_string(),
+                labels: labels(),
+            })?,
+        None => match dimmables.as_slice() {
+            [dimmable] => dimmable,
+            [] => return Err(Error::NotDimmable(device_id.to_string())),
+            _ => {
+                return Err(Error::AmbiguousDimmable {
+                    device_id: device_id.to_string(),
+                    labels: labels(),
+                })
+            }
+        },
+    };
+
+    check_range(dimmable, value)?;
+
+    debug!(
+        "Setting `{}` of device `{device_id}` to {value}...",
+        dimmable.label
+    );
+    api::dim(client, device_id, &dimmable.label, value)
+        .await
+        .map_err(|err| match err {
+            SdkError::Smarthome(status_code) => match status_code {
+                StatusCode::UNPROCESSABLE_ENTITY => Error::InvalidDevice(device_id.to_string()),
+                StatusCode::FORBIDDEN => Error::PermissionDenied(device_id.to_string()),
+                StatusCode::SERVICE_UNAVAILABLE => Error::ServerError,
+                _ => Error::Unknown(err),
+            },
+            _ => Error::Unknown(err),
+        })?;
+    info!(
+        "Successfully set `{}` of device `{device_id}` to {value}",
+        dimmable.label
+    );
+    Ok(())
+}
+
+/// Both bounds of the dimmable's range are inclusive
+fn check_range(dimmable: &DeviceDimmable, value: f64) -> Result<()> {
+    if !(dimmable.range.lower..=dimmable.range.upper).contains(&value) {
+        return Err(Error::OutOfRange {
+            label: dimmable.label.clone(),
+            value,
+            lower: dimmable.range.lower,
+            upper: dimmable.range.upper,
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use smarthome_sdk_rs::DimmableRange;
+
+    use super::*;
+
+    fn brightness() -> DeviceDimmable {
+        DeviceDimmable {
+            value: 50.0,
+            label: "brightness".to_string(),
+            range: DimmableRange {
+                lower: 0.0,
+                upper: 100.0,
+            },
+        }
+    }
+
+    #[test]
+    fn range_bounds_are_inclusive() {
+        assert!(check_range(&brightness(), 0.0).is_ok());
+        assert!(check_range(&brightness(), 50.0).is_ok());
+        assert!(check_range(&brightness(), 100.0).is_ok());
+    }
+
+    #[test]
+    fn values_outside_the_range_are_rejected() {
+        assert!(matches!(
+            check_range(&brightness(), -0.5),
+            Err(Error::OutOfRange { .. })
+        ));
+        assert!(matches!(
+            check_range(&brightness(), 100.5),
+            Err(Error::OutOfRange { .. })
+        ));
+    }
+}
@@ -0,0 +1,78 @@
+use smarthome_sdk_rs::Error as SdkError;
+use std::fmt::Display;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[allow(clippy::enum_variant_names)]
+pub enum Error {
+    GetDevices(SdkError),
+    DeviceNotFound(String),
+    NotDimmable(String),
+    UnknownDimmable {
+        device_id: String,
+        label: String,
+        labels: Vec<String>,
+    },
+    AmbiguousDimmable {
+        device_id: String,
+        labels: Vec<String>,
+    },
+    OutOfRange {
+        label: String,
+        value: f64,
+        lower: f64,
+        upper: f64,
+    },
+    InvalidDevice(String),
+    PermissionDenied(String),
+    ServerError,
+    Unknown(SdkError),
+}
+
+fn label_list(labels: &[String]) -> String {
+    labels
+        .iter()
+        .map(|label| format!("`{label}`"))
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::GetDevices(err) => format!("Could not get devices: {err}"),
+                Self::DeviceNotFound(id) =>
+                    format!("The device `{id}` does not exist or is inaccessible"),
+                Self::NotDimmable(id) => format!("The device `{id}` is not dimmable"),
+                Self::UnknownDimmable {
+                    device_id,
+                    label,
+                    labels,
+                } => format!(
+                    "The device `{device_id}` has no dimmable `{label}`\n => Available dimmables: {}",
+                    label_list(labels)
+                ),
+                Self::AmbiguousDimmable { device_id, labels } => format!(
+                    "The device `{device_id}` has {} dimmables\n => Select one using `--label`: {}",
+                    labels.len(),
+                    label_list(labels)
+                ),
+                Self::OutOfRange {
+                    label,
+                    value,
+                    lower,
+                    upper,
+                } => format!(
+                    "The value {value} is out of range for `{label}`: it must be at least {lower} and at most {upper}"
+                ),
+                Self::InvalidDevice(id) => format!("The device `{id}` does not exist"),
+                Self::PermissionDenied(id) => format!("You are either lacking permission to use devices or you do not have access to the device `{id}`"),
+                Self::ServerError => "The server was unable to handle this device".to_string(),
+                Self::Unknown(err) => format!("Unknown error: {err}"),
+            }
+        )
+    }
+}
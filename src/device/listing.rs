@@ -0,0 +1,211 @@
+use smarthome_sdk_rs::{
+    Client, DeviceCapability, DeviceDimmable, DeviceSensor, DeviceType, HydratedDeviceResponse,
+};
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    capability_name,
+    errors::{Error, Result},
+    find_device,
+};
+use crate::cli::Capability;
+
+#[derive(Tabled)]
+struct TableDevice {
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Room ID")]
+    room_id: String,
+    #[tabled(rename = "Type")]
+    type_: String,
+    #[tabled(rename = "Driver")]
+    driver: String,
+    #[tabled(rename = "Capabilities")]
+    capabilities: String,
+}
+
+#[derive(Tabled)]
+struct TableDimmable {
+    #[tabled(rename = "Label")]
+    label: String,
+    #[tabled(rename = "Value")]
+    value: f64,
+    #[tabled(rename = "Range")]
+    range: String,
+}
+
+#[derive(Tabled)]
+struct TableSensor {
+    #[tabled(rename = "Label")]
+    label: String,
+    #[tabled(rename = "Value")]
+    value: String,
+    #[tabled(rename = "Unit")]
+    unit: String,
+    #[tabled(rename = "Type")]
+    hms_type: String,
+}
+
+impl From<HydratedDeviceResponse> for TableDevice {
+    fn from(source: HydratedDeviceResponse) -> Self {
+        Self {
+            id: source.shallow.id,
+            name: source.shallow.name,
+            room_id: source.shallow.room_id,
+            type_: type_name(&source.shallow.type_).to_string(),
+            driver: format!("{}:{}", source.shallow.vendor_id, source.shallow.model_id),
+            capabilities: capability_list(&source.extractions.config.capabilities),
+        }
+    }
+}
+
+impl From<DeviceDimmable> for TableDimmable {
+    fn from(source: DeviceDimmable) -> Self {
+        Self {
+            label: source.label,
+            value: source.value,
+            range: format!("{} - {}", source.range.lower, source.range.upper),
+        }
+    }
+}
+
+impl From<DeviceSensor> for TableSensor {
+    fn from(source: DeviceSensor) -> Self {
+        Self {
+            label: source.label,
+            value: match source.value {
+                serde_json::Value::String(value) => value,
+                value => value.to_string(),
+            },
+            unit: source.unit,
+            hms_type: source.hms_type,
+        }
+    }
+}
+
+fn type_name(type_: &DeviceType) -> &'static str {
+    match type_ {
+        DeviceType::Input => "input",
+        DeviceType::Output => "output",
+    }
+}
+
+fn capability_list(capabilities: &[DeviceCapability]) -> String {
+    capabilities
+        .iter()
+        .map(capability_name)
+        .collect::<Vec<&str>>()
+        .join(", ")
+}
+
+fn print_table<T: Tabled>(rows: impl IntoIterator<Item = T>) {
+    let mut table = Table::new(rows);
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+}
+
+/// Lists the user's devices or all devices which have all of the given capabilities
+pub async fn list(client: &Client, show_all: bool, capabilities: &[Capability]) -> Result<()> {
+    let devices = match show_all {
+        true => client.all_switches().await,
+        false => client.personal_switches().await,
+    }
+    .map_err(Error::GetDevices)?;
+
+    let required: Vec<DeviceCapability> = capabilities
+        .iter()
+        .map(|capability| DeviceCapability::from(*capability))
+        .collect();
+    print_table(
+        devices
+            .into_iter()
+            .filter(|device| {
+                required
+                    .iter()
+                    .all(|capability| device.extractions.config.capabilities.contains(capability))
+            })
+            .map(TableDevice::from),
+    );
+    Ok(())
+}
+
+/// Shows everything the server reports about a single device
+pub async fn show(client: &Client, id: &str) -> Result<()> {
+    let device = find_device(client, id).await?;
+    let shallow = device.shallow;
+    let extractions = device.extractions;
+
+    println!(
+        "=== {} ({}) ===
+  Type          {}
+  Room          {}
+  Vendor        {}
+  Model         {}
+  Capabilities  {}",
+        shallow.name,
+        shallow.id,
+        type_name(&shallow.type_),
+        shallow.room_id,
+        shallow.vendor_id,
+        shallow.model_id,
+        capability_list(&extractions.config.capabilities),
+    );
+
+    if let Some(power) = extractions.power_information {
+        println!(
+            "\n=== Power ===
+  State  {}
+  Draw   {} W",
+            match power.state {
+                true => "\x1b[1;32mON\x1b[1;0m",
+                false => "\x1b[1;31mOFF\x1b[1;0m",
+            },
+            power.power_draw_watts
+        );
+    }
+
+    if let Some(dimmables) = extractions.dimmables.filter(|d| !d.is_empty()) {
+        println!("\n=== Dimmables ===");
+        print_table(dimmables.into_iter().map(TableDimmable::from));
+    }
+
+    if let Some(sensors) = extractions.sensors.filter(|s| !s.is_empty()) {
+        println!("\n=== Sensors ===");
+        print_table(sensors.into_iter().map(TableSensor::from));
+    }
+
+    println!("\n=== Configuration ===");
+    print_json(&extractions.config.info);
+    println!("\n=== Driver ===");
+    print_json(&shallow.singleton_json);
+
+    if !extractions.hms_errors.is_empty() {
+        println!("\n=== \x1b[1;31mDriver Errors\x1b[1;0m ===");
+        for err in &extractions.hms_errors {
+            println!("  {err}");
+        }
+    }
+    Ok(())
+}
+
+fn print_json(value: &serde_json::Value) {
+    match value {
+        serde_json::Value::Null => println!("  \x1b[1;30mN/A\x1b[1;0m"),
+        value => {
+            let json =
+                serde_json::to_string_pretty(value).expect("JSON values can always be serialized");
+            for line in json.lines() {
+                println!("  {line}");
+            }
+        }
+    }
+}
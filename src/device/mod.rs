@@ -0,0 +1,58 @@
+use smarthome_sdk_rs::{Client, DeviceCapability, HydratedDeviceResponse};
+
+use crate::cli::{Capability, DeviceCommand};
+use errors::{Error, Result};
+
+mod api;
+mod dim;
+mod errors;
+mod listing;
+
+pub async fn handle_subcommand(command: DeviceCommand, client: &Client) -> Result<()> {
+    match command {
+        DeviceCommand::Ls { all, capability } => listing::list(client, all, &capability).await,
+        DeviceCommand::Show { id } => listing::show(client, &id).await,
+        DeviceCommand::Dim { id, value, label } => {
+            dim::set_dimmable(client, &id, label.as_deref(), value).await
+        }
+    }
+}
+
+impl From<Capability> for DeviceCapability {
+    fn from(source: Capability) -> Self {
+        match source {
+            Capability::Base => Self::Base,
+            Capability::Power => Self::Power,
+            Capability::Dimmable => Self::Dimmable,
+            Capability::Sensor => Self::Sensor,
+        }
+    }
+}
+
+fn capability_name(capability: &DeviceCapability) -> &'static str {
+    match capability {
+        DeviceCapability::Base => "base",
+        DeviceCapability::Power => "power",
+        DeviceCapability::Dimmable => "dimmable",
+        DeviceCapability::Sensor => "sensor",
+    }
+}
+
+/// Looks up a device by its ID
+/// The user's personal devices are searched first, then all devices on the server
+async fn find_device(client: &Client, id: &str) -> Result<HydratedDeviceResponse> {
+    let personal = client
+        .personal_switches()
+        .await
+        .map_err(Error::GetDevices)?;
+    if let Some(device) = personal.into_iter().find(|device| device.shallow.id == id) {
+        return Ok(device);
+    }
+    // Listing all devices requires a permission which the user might not have
+    client
+        .all_switches()
+        .await
+        .ok()
+        .and_then(|all| all.into_iter().find(|device| device.shallow.id == id))
+        .ok_or_else(|| Error::DeviceNotFound(id.to_string()))
+}
@@ -4,10 +4,13 @@ use std::{
     path::Path,
 };
 
-use crate::hms::workspace::HomescriptMetadata;
+use crate::{
+    automation::api as automation_api,
+    hms::{imports, workspace::HomescriptMetadata},
+};
 
 use super::errors::{Error, Result};
-use log::{debug, info};
+use log::{debug, info, warn};
 use reqwest::StatusCode;
 use smarthome_sdk_rs::{Client, Error as SdkError, HomescriptData, HomescriptType};
 
@@ -24,21 +27,13 @@ pub async fn create_script(
         return Err(Error::ScriptAlreadyExists(id));
     }
 
-    if id.contains(' ') || id.len() > 30 {
-        return Err(Error::InvalidData(
-            "id must not contain whitespaces and shall not exceed 30 characters".to_string(),
-        ));
-    }
+    validate_id(&id)?;
     if name.len() > 30 {
         return Err(Error::InvalidData(
             "name must not exceed 30 characters".to_string(),
         ));
     }
-    if workspace.len() > 50 {
-        return Err(Error::InvalidData(
-            "workspace must not exceed 50 characters".to_string(),
-        ));
-    }
+    validate_workspace(&workspace)?;
 
     debug!("Creating script `{id}` at `./{id}`...");
     match client
@@ -63,7 +58,11 @@ pub async fn create_script(
 
             let mut metadate_file = File::create(path.join(".hms.toml"))?;
             metadate_file.write_all(
-                toml::to_string_pretty(&HomescriptMetadata { id: id.clone(), is_driver: false })?.as_bytes(),
+                toml::to_string_pretty(&HomescriptMetadata {
+                    id: id.clone(),
+                    is_driver: false,
+                })?
+                .as_bytes(),
             )?;
 
             info!("Successfully created script `{id}`");
@@ -102,3 +101,179 @@ pub async fn delete_script(client: &Client, id: &str) -> Result<()> {
         }),
     }
 }
+
+pub async fn move_script(
+    client: &Client,
+    old_id: &str,
+    new_id: Option<String>,
+    workspace: Option<String>,
+) -> Result<()> {
+    let mut scripts = client.list_personal_homescripts().await?;
+    let old_data = match scripts.iter().position(|script| script.data.id == old_id) {
+        Some(index) => scripts.swap_remove(index).data,
+        None => return Err(Error::ScriptDoesNotExist(old_id.to_string())),
+    };
+
+    let new_id = new_id.unwrap_or_else(|| old_id.to_string());
+    let workspace = workspace.unwrap_or_else(|| old_data.workspace.clone());
+    validate_workspace(&workspace)?;
+
+    // If the ID stays the same, only the workspace needs to be modified
+    if new_id == old_id {
+        if workspace == old_data.workspace {
+            info!("Script `{old_id}` is already in workspace `{workspace}`");
+            return Ok(());
+        }
+        debug!("Moving script `{old_id}` to workspace `{workspace}`...");
+        client
+            .modify_homescript(&HomescriptData {
+                workspace: workspace.clone(),
+                ..old_data
+            })
+            .await?;
+        info!("Successfully moved script `{old_id}` to workspace `{workspace}`");
+        return Ok(());
+    }
+
+    validate_id(&new_id)?;
+    if scripts.iter().any(|script| script.data.id == new_id) || Path::new(&new_id).exists() {
+        return Err(Error::ScriptAlreadyExists(new_id));
+    }
+
+    // Automations would prevent the deletion of the old script, so the move is refused before anything is created
+    // If the automations cannot be fetched, the server still refuses the deletion and the new script is removed again
+    match automation_api::list_personal(client).await {
+        Ok(automations) => {
+            let automations: Vec<String> = automations
+                .into_iter()
+                .filter(|automation| automation.homescript_id == old_id)
+                .map(|automation| automation.name)
+                .collect();
+            if !automations.is_empty() {
+                return Err(Error::ScriptUsedByAutomations {
+                    id: old_id.to_string(),
+                    automations,
+                });
+            }
+        }
+        Err(err) => debug!("Could not fetch automations: {err}"),
+    }
+
+    // Create the new script using the old data
+    debug!("Creating script `{new_id}` from `{old_id}`...");
+    let is_driver = old_data.type_ == HomescriptType::Driver;
+    if let Err(err) = client
+        .create_homescript(&HomescriptData {
+            id: new_id.clone(),
+            workspace,
+            ..old_data
+        })
+        .await
+    {
+        return Err(match err {
+            SdkError::Smarthome(StatusCode::UNPROCESSABLE_ENTITY) => {
+                Error::ScriptAlreadyExists(new_id)
+            }
+            _ => Error::Smarthome(err),
+        });
+    }
+
+    // Delete the old script, if this fails, the newly created script is removed again
+    debug!("Deleting old script `{old_id}`...");
+    if let Err(err) = client.delete_homescript(old_id).await {
+        debug!("Could not delete old script `{old_id}`, rolling back...");
+        // The original error is always returned, a failed rollback is only reported
+        if let Err(rollback_err) = client.delete_homescript(&new_id).await {
+            warn!("Could not remove the new script `{new_id}` again, it duplicates `{old_id}` and has to be deleted manually: {rollback_err}");
+        }
+        return Err(match err {
+            SdkError::Smarthome(StatusCode::UNPROCESSABLE_ENTITY) => {
+                Error::ScriptDoesNotExist(old_id.to_string())
+            }
+            SdkError::Smarthome(StatusCode::CONFLICT) => {
+                Error::ScriptHasDependentAutomations(old_id.to_string())
+            }
+            _ => Error::Smarthome(err),
+        });
+    }
+
+    // Rewrite the imports of other scripts which depend on the old ID
+    for script in scripts {
+        let Some(code) = imports::rewrite_imports(&script.data.code, old_id, &new_id) else {
+            continue;
+        };
+        let id = script.data.id.clone();
+        match client
+            .modify_homescript(&HomescriptData {
+                code,
+                ..script.data
+            })
+            .await
+        {
+            Ok(_) => info!("Updated imports of script `{id}`"),
+            Err(err) => {
+                // The local clone is left untouched so that it still matches the server
+                warn!("Could not update imports of script `{id}`: {err}");
+                continue;
+            }
+        }
+
+        // Also update the local clone of the dependent script (if it exists)
+        let local_path = Path::new(&id).join(format!("{id}.hms"));
+        if local_path.exists() {
+            if let Some(code) =
+                imports::rewrite_imports(&fs::read_to_string(&local_path)?, old_id, &new_id)
+            {
+                fs::write(local_path, code)?;
+            }
+        }
+    }
+
+    rename_local_clone(old_id, &new_id, is_driver)?;
+
+    info!("Successfully moved script `{old_id}` to `{new_id}`");
+    Ok(())
+}
+
+/// Renames the local clone directory of a script, including its code file and manifest
+fn rename_local_clone(old_id: &str, new_id: &str, is_driver: bool) -> Result<()> {
+    let old_path = Path::new(old_id);
+    if !old_path.exists() {
+        return Ok(());
+    }
+    debug!("Renaming local clone `./{old_id}` to `./{new_id}`...");
+    let new_path = Path::new(new_id);
+    fs::rename(old_path, new_path)?;
+
+    let old_homescript_path = new_path.join(format!("{old_id}.hms"));
+    if old_homescript_path.exists() {
+        fs::rename(old_homescript_path, new_path.join(format!("{new_id}.hms")))?;
+    }
+
+    fs::write(
+        new_path.join(".hms.toml"),
+        toml::to_string_pretty(&HomescriptMetadata {
+            id: new_id.to_string(),
+            is_driver,
+        })?,
+    )?;
+    Ok(())
+}
+
+fn validate_id(id: &str) -> Result<()> {
+    if id.contains(' ') || id.len() > 30 {
+        return Err(Error::InvalidData(
+            "id must not contain whitespaces and shall not exceed 30 characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_workspace(workspace: &str) -> Result<()> {
+    if workspace.len() > 50 {
+        return Err(Error::InvalidData(
+            "workspace must not exceed 50 characters".to_string(),
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,179 @@
+use log::{debug, warn};
+use smarthome_sdk_rs::Client;
+
+use super::{
+    errors::{Error, Result},
+    imports,
+};
+use crate::automation::api::{self as automation_api, Automation};
+
+/// A module which is imported by the inspected script
+struct Dependency {
+    module: String,
+    // Whether the module is one of the user's scripts (in contrast to a built-in or foreign module)
+    is_script: bool,
+}
+
+pub async fn show_dependencies(client: &Client, id: &str, as_dot: bool) -> Result<()> {
+    let scripts = client.list_personal_homescripts().await?;
+    let Some(script) = scripts.iter().find(|script| script.data.id == id) else {
+        return Err(Error::ScriptDoesNotExist(id.to_string()));
+    };
+
+    // The automations are optional as the user might lack permission to use them
+    debug!("Fetching automations...");
+    let automations: Vec<Automation> = match automation_api::list_personal(client).await {
+        Ok(automations) => automations
+            .into_iter()
+            .filter(|automation| automation.homescript_id == id)
+            .collect(),
+        Err(err) => {
+            warn!("Could not fetch automations, they are excluded from the output: {err}");
+            vec![]
+        }
+    };
+
+    let dependent_scripts: Vec<&str> = scripts
+        .iter()
+        .filter(|other| other.data.id != id)
+        .filter(|other| {
+            imports::imported_modules(&other.data.code)
+                .iter()
+                .any(|module| module == id)
+        })
+        .map(|other| other.data.id.as_str())
+        .collect();
+
+    let dependencies: Vec<Dependency> = imports::imported_modules(&script.data.code)
+        .into_iter()
+        .map(|module| Dependency {
+            is_script: scripts.iter().any(|script| script.data.id == module),
+            module,
+        })
+        .collect();
+
+    println!(
+        "{}",
+        match as_dot {
+            true => render_dot(id, &dependencies, &dependent_scripts, &automations),
+            false => render_text(id, &dependencies, &dependent_scripts, &automations),
+        }
+    );
+    Ok(())
+}
+
+fn render_text(
+    id: &str,
+    dependencies: &[Dependency],
+    dependent_scripts: &[&str],
+    automations: &[Automation],
+) -> String {
+    let mut output = vec![format!("\x1b[1;32m=== Script `{id}` ===\x1b[0m")];
+
+    output.push("Depends on:".to_string());
+    if dependencies.is_empty() {
+        output.push("  (nothing)".to_string());
+    }
+    for dependency in dependencies {
+        output.push(format!(
+            "  • {} ({})",
+            dependency.module,
+            match dependency.is_script {
+                true => "script",
+                false => "built-in or foreign module",
+            }
+        ));
+    }
+
+    output.push("Required by:".to_string());
+    if dependent_scripts.is_empty() && automations.is_empty() {
+        output.push("  (nothing)".to_string());
+    }
+    for script in dependent_scripts {
+        output.push(format!("  • {script} (script)"));
+    }
+    for automation in automations {
+        output.push(format!(
+            "  • {} (automation {}, {})",
+            automation.name,
+            automation.id,
+            match automation.enabled {
+                true => "enabled",
+                false => "disabled",
+            }
+        ));
+    }
+    output.join("\n")
+}
+
+fn render_dot(
+    id: &str,
+    dependencies: &[Dependency],
+    dependent_scripts: &[&str],
+    automations: &[Automation],
+) -> String {
+    let mut output = vec![
+        format!("digraph {} {{", dot_quote(id)),
+        format!(
+            "  {} [label={}, shape=box, style=bold];",
+            dot_quote(&format!("script:{id}")),
+            dot_quote(id)
+        ),
+    ];
+
+    for dependency in dependencies {
+        let node = match dependency.is_script {
+            true => format!("script:{}", dependency.module),
+            false => format!("module:{}", dependency.module),
+        };
+        output.push(format!(
+            "  {} [label={}, shape={}];",
+            dot_quote(&node),
+            dot_quote(&dependency.module),
+            match dependency.is_script {
+                true => "box",
+                false => "ellipse",
+            }
+        ));
+        output.push(format!(
+            "  {} -> {};",
+            dot_quote(&format!("script:{id}")),
+            dot_quote(&node)
+        ));
+    }
+
+    for script in dependent_scripts {
+        output.push(format!(
+            "  {} [label={}, shape=box];",
+            dot_quote(&format!("script:{script}")),
+            dot_quote(script)
+        ));
+        output.push(format!(
+            "  {} -> {};",
+            dot_quote(&format!("script:{script}")),
+            dot_quote(&format!("script:{id}"))
+        ));
+    }
+
+    for automation in automations {
+        let node = format!("automation:{}", automation.id);
+        output.push(format!(
+            "  {} [label={}, shape=diamond];",
+            dot_quote(&node),
+            dot_quote(&automation.name)
+        ));
+        output.push(format!(
+            "  {} -> {};",
+            dot_quote(&node),
+            dot_quote(&format!("script:{id}"))
+        ));
+    }
+
+    output.push("}".to_string());
+    output.join("\n")
+}
+
+/// Creates a quoted DOT identifier
+fn dot_quote(input: &str) -> String {
+    format!("\"{}\"", input.replace('\\', "\\\\").replace('"', "\\\""))
+}
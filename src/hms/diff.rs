@@ -0,0 +1,35 @@
+/// Creates a colored line-based diff between two versions of Homescript code
+/// Uses the longest common subsequence of lines, which is sufficient for the size of typical scripts
+pub fn line_diff(old: &str, new: &str) -> String {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+
+    // lcs[i][j] holds the length of the longest common subsequence of `old[i..]` and `new[j..]`
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut output = vec![];
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            output.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            output.push(format!("\x1b[1;32m+ {}\x1b[1;0m", new[j]));
+            j += 1;
+        } else {
+            output.push(format!("\x1b[1;31m- {}\x1b[1;0m", old[i]));
+            i += 1;
+        }
+    }
+    output.join("\n")
+}
@@ -4,11 +4,18 @@ pub type Result<T> = std::result::Result<T, Error>;
 
 use smarthome_sdk_rs::{Error as SdkError, HomescriptExecError};
 
+use crate::config;
+
 pub enum Error {
     Rustyline(rustyline::error::ReadlineError),
     FetchHomescript(SdkError),
     ScriptDoesNotExist(String),
     ScriptHasDependentAutomations(String),
+    // The script cannot be moved as automations refer to its ID, holds the script's ID and the automations' names
+    ScriptUsedByAutomations {
+        id: String,
+        automations: Vec<String>,
+    },
     IO(io::Error),
     ScriptAlreadyExists(String),
     InvalidData(String),
@@ -27,6 +34,12 @@ pub enum Error {
     InvalidHomescript(String),
     DecodeManifest(toml::de::Error),
     CloneDirAlreadyExists(String),
+    InvalidServer(String),
+    Connect {
+        server_id: String,
+        err: SdkError,
+    },
+    Credentials(config::Error),
     Smarthome(SdkError),
 }
 
@@ -76,7 +89,9 @@ impl Display for Error {
                         Self::ScriptDoesNotExist(id) =>
                             format!("Script `{id}` does not exist or is inaccessible"),
                         Self::ScriptHasDependentAutomations(id) =>
-                            format!("Automations depend on script `{id}`"),
+                            format!("Automations depend on script `{id}`\n => Show all dependents using `hms script deps {id}`"),
+                        Self::ScriptUsedByAutomations { id, automations } =>
+                            format!("Cannot move script `{id}`: it is used by the automation(s) {}\n => Delete these automations or change their script first", automations.iter().map(|name| format!("`{name}`")).collect::<Vec<String>>().join(", ")),
                         Self::DecodeManifest(err) => format!("Invalid Homescript manifest (at `.hms.toml`):\n{err}\n => Clone this script again"),
                         Self::NotAWorkspace =>
                         "Not a valid Homescript directory: (missing files?)".to_string(),
@@ -99,6 +114,9 @@ impl Display for Error {
                         } ,
                         Self::Smarthome(err) => format!("Smarthome Error: {err}"),
                         Self::CloneDirAlreadyExists(path) => format!("Cannot clone: directory at `./{path}` already exists."),
+                        Self::InvalidServer(id) => format!("Invalid server id: the id `{id}` was not found in the server list"),
+                        Self::Connect { server_id, err } => format!("Could not connect to server `{server_id}`: {err}"),
+                        Self::Credentials(err) => err.to_string(),
                 Self::Rustyline(err) => format!("REPL error: {err}"),
                 Self::FetchHomescript(err) => format!("Could not fetch Homescript: {err}"),
             }
@@ -0,0 +1,197 @@
+use std::ops::Range;
+
+/// A single token of Homescript source code, only identifiers and punctuation are kept
+struct Token<'src> {
+    text: &'src str,
+    span: Range<usize>,
+}
+
+/// Splits Homescript code into identifiers and punctuation
+/// Comments and string literals are skipped so that their contents are never mistaken for imports
+fn tokenize(code: &str) -> Vec<Token<'_>> {
+    let bytes = code.as_bytes();
+    let mut tokens = vec![];
+    let mut idx = 0;
+
+    while idx < bytes.len() {
+        match bytes[idx] {
+            b'/' if bytes.get(idx + 1) == Some(&b'/') => {
+                while idx < bytes.len() && bytes[idx] != b'\n' {
+                    idx += 1;
+                }
+            }
+            b'/' if bytes.get(idx + 1) == Some(&b'*') => {
+                idx += 2;
+                while idx < bytes.len()
+                    && !(bytes[idx] == b'*' && bytes.get(idx + 1) == Some(&b'/'))
+                {
+                    idx += 1;
+                }
+                idx += 2;
+            }
+            quote @ (b'"' | b'\'' | b'`') => {
+                idx += 1;
+                while idx < bytes.len() && bytes[idx] != quote {
+                    if bytes[idx] == b'\\' {
+                        idx += 1;
+                    }
+                    idx += 1;
+                }
+                idx += 1;
+            }
+            byte if byte.is_ascii_alphanumeric() || byte == b'_' => {
+                let start = idx;
+                while idx < bytes.len()
+                    && (bytes[idx].is_ascii_alphanumeric() || bytes[idx] == b'_')
+                {
+                    idx += 1;
+                }
+                tokens.push(Token {
+                    text: &code[start..idx],
+                    span: start..idx,
+                });
+            }
+            byte if byte.is_ascii_whitespace() || !byte.is_ascii() => idx += 1,
+            _ => {
+                tokens.push(Token {
+                    text: &code[idx..idx + 1],
+                    span: idx..idx + 1,
+                });
+                idx += 1;
+            }
+        }
+    }
+    tokens
+}
+
+/// Returns the source spans of every module name which follows an `import ... from` statement
+fn import_sources(code: &str) -> Vec<(&str, Range<usize>)> {
+    let tokens = tokenize(code);
+    let mut sources = vec![];
+
+    for (idx, token) in tokens.iter().enumerate() {
+        if token.text != "import" {
+            continue;
+        }
+        // Search for the `from` keyword which belongs to this import statement
+        let statement = tokens[idx + 1..]
+            .iter()
+            .take_while(|token| token.text != ";" && token.text != "import");
+        let mut statement = statement.skip_while(|token| token.text != "from").skip(1);
+        if let Some(module) = statement.next() {
+            sources.push((module.text, module.span.clone()));
+        }
+    }
+    sources
+}
+
+/// Replaces every import of the module `old` with the module `new`
+/// Returns `None` if the code does not import `old` at all
+pub fn rewrite_imports(code: &str, old: &str, new: &str) -> Option<String> {
+    let spans: Vec<Range<usize>> = import_sources(code)
+        .into_iter()
+        .filter(|(module, _)| *module == old)
+        .map(|(_, span)| span)
+        .collect();
+
+    if spans.is_empty() {
+        return None;
+    }
+
+    let mut output = String::with_capacity(code.len());
+    let mut last = 0;
+    for span in spans {
+        output.push_str(&code[last..span.start]);
+        output.push_str(new);
+        last = span.end;
+    }
+    output.push_str(&code[last..]);
+    Some(output)
+}
+
+/// Returns the names of all modules which are imported by the given Homescript code
+pub fn imported_modules(code: &str) -> Vec<String> {
+    let mut modules: Vec<String> = vec![];
+    for (module, _) in import_sources(code) {
+        if !modules.iter().any(|existing| existing == module) {
+            modules.push(module.to_string());
+        }
+    }
+    modules
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rewrites_single_import() {
+        assert_eq!(
+            rewrite_imports("import { add } from math;\nadd(1, 2);", "math", "calc").as_deref(),
+            Some("import { add } from calc;\nadd(1, 2);")
+        );
+    }
+
+    #[test]
+    fn rewrites_multiple_imports() {
+        let code = "import { a } from math;\nimport { b } from other;\nimport { c } from math;";
+        assert_eq!(
+            rewrite_imports(code, "math", "calc").as_deref(),
+            Some("import { a } from calc;\nimport { b } from other;\nimport { c } from calc;")
+        );
+        assert_eq!(imported_modules(code), vec!["math", "other"]);
+    }
+
+    #[test]
+    fn only_rewrites_exact_module_names() {
+        assert_eq!(
+            rewrite_imports("import { a } from mathx;", "math", "calc"),
+            None
+        );
+        assert_eq!(
+            rewrite_imports("let math = 1; math + 1;", "math", "calc"),
+            None
+        );
+    }
+
+    #[test]
+    fn ignores_imports_in_comments() {
+        let code = "// import { a } from math;\n/* import { b } from math; */\nlet x = 1;";
+        assert_eq!(rewrite_imports(code, "math", "calc"), None);
+        assert!(imported_modules(code).is_empty());
+    }
+
+    #[test]
+    fn ignores_imports_in_strings() {
+        let code = "let s = \"import { a } from math;\";\nlet t = 'from math';\nlet u = `\\` import { x } from math`;";
+        assert_eq!(rewrite_imports(code, "math", "calc"), None);
+    }
+
+    #[test]
+    fn keeps_strings_and_comments_around_imports() {
+        let code = "import { a } from math; // uses math\nprintln(\"math\");";
+        assert_eq!(
+            rewrite_imports(code, "math", "calc").as_deref(),
+            Some("import { a } from calc; // uses math\nprintln(\"math\");")
+        );
+    }
+
+    #[test]
+    fn unterminated_strings_and_comments() {
+        for code in [
+            "import { a } from math; let s = \"import { b } from math;",
+            "import { a } from math; /* import { b } from math;",
+            "import { a } from math; let s = 'escaped \\",
+            "import { a } from math; /",
+        ] {
+            assert_eq!(imported_modules(code), vec!["math"], "{code}");
+        }
+    }
+
+    #[test]
+    fn incomplete_import_statements() {
+        assert!(imported_modules("import { a }").is_empty());
+        assert!(imported_modules("import { a } from").is_empty());
+        assert!(imported_modules("import").is_empty());
+    }
+}
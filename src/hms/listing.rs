@@ -1,6 +1,6 @@
 use std::vec;
 
-use smarthome_sdk_rs::{Client, HmsRunMode, Homescript};
+use smarthome_sdk_rs::{Client, Homescript};
 use tabled::{
     settings::{object::Rows, Format, Modify, Style},
     Table, Tabled,
@@ -7,8 +7,12 @@ use crate::{
 use errors::Result;
 
 mod crud;
+mod deps;
+mod diff;
 mod errors;
+mod imports;
 mod listing;
+mod promote;
 mod repl;
 mod run;
 mod workspace;
@@ -17,6 +21,7 @@ pub async fn handle_subcommand(
     command: HmsCommand,
     client: &Client,
     config: &Config,
+    version_check: bool,
 ) -> Result<()> {
     match command {
         HmsCommand::Repl => repl::start(client).await?,
@@ -46,6 +51,37 @@ pub async fn handle_subcommand(
                     crud::delete_script(client, script_id).await?
                 }
             }
+            HmsScriptCommand::Mv {
+                old_id,
+                new_id,
+                workspace,
+            } => crud::move_script(client, &old_id, new_id, workspace).await?,
+            HmsScriptCommand::Deps { id, dot } => deps::show_dependencies(client, &id, dot).await?,
+            HmsScriptCommand::Promote {
+                ids,
+                workspace,
+                from,
+                to,
+                dry_run,
+                force,
+                yes,
+            } => {
+                promote::promote(
+                    client,
+                    config,
+                    promote::PromoteOptions {
+                        ids,
+                        workspace,
+                        from,
+                        to,
+                        dry_run,
+                        force,
+                        yes,
+                        version_check,
+                    },
+                )
+                .await?
+            }
             HmsScriptCommand::Clone { ids, all } => workspace::clone(&ids, all, client).await?,
             HmsScriptCommand::Push { force } => {
                 workspace::push(client, config.homescript.lint_on_push, force).await?
@@ -0,0 +1,220 @@
+use std::io::{self, Write};
+
+use log::{debug, info, warn};
+use smarthome_sdk_rs::{Client, HmsRunMode, Homescript, HomescriptData, HomescriptType};
+
+use super::{
+    diff,
+    errors::{Error, Result},
+};
+use crate::config::Config;
+
+pub struct PromoteOptions {
+    pub ids: Vec<String>,
+    pub workspace: Option<String>,
+    pub from: Option<String>,
+    pub to: String,
+    pub dry_run: bool,
+    pub force: bool,
+    pub yes: bool,
+    pub version_check: bool,
+}
+
+/// A script which is going to be written to the target server
+struct PromoteStep {
+    data: HomescriptData,
+    // The code of the script on the target, `None` if the script does not exist there yet
+    target_code: Option<String>,
+}
+
+pub async fn promote(client: &Client, config: &Config, options: PromoteOptions) -> Result<()> {
+    // Connect to the source server, the currently selected server is used if none was specified
+    let source_client;
+    let source = match &options.from {
+        Some(from) => {
+            source_client = connect(config, from, options.version_check).await?;
+            &source_client
+        }
+        None => client,
+    };
+    let target = connect(config, &options.to, options.version_check).await?;
+
+    if source.smarthome_url == target.smarthome_url && source.username == target.username {
+        return Err(Error::InvalidData(
+            "source and target refer to the same server and user".to_string(),
+        ));
+    }
+
+    // Select the scripts which should be promoted
+    let source_scripts = source.list_personal_homescripts().await?;
+    let scripts: Vec<HomescriptData> = match &options.workspace {
+        Some(workspace) => {
+            let scripts: Vec<HomescriptData> = source_scripts
+                .into_iter()
+                .filter(|script| script.data.workspace == *workspace)
+                .map(|script| script.data)
+                .collect();
+            if scripts.is_empty() {
+                return Err(Error::InvalidData(format!(
+                    "workspace `{workspace}` does not contain any scripts"
+                )));
+            }
+            scripts
+        }
+        None => select_scripts(source_scripts, &options.ids)?,
+    };
+
+    // Compare the scripts with their target counterparts
+    let target_scripts = target.list_personal_homescripts().await?;
+    let mut steps = vec![];
+    for data in scripts {
+        let target_data = target_scripts
+            .iter()
+            .find(|script| script.data.id == data.id)
+            .map(|script| &script.data);
+
+        if let Some(target_data) = target_data {
+            if is_same_data(&data, target_data) {
+                info!(
+                    "Script `{}` is already up to date on `{}`",
+                    data.id, options.to
+                );
+                continue;
+            }
+        }
+        steps.push(PromoteStep {
+            target_code: target_data.map(|data| data.code.clone()),
+            data,
+        });
+    }
+
+    if steps.is_empty() {
+        info!("Nothing to promote.");
+        return Ok(());
+    }
+
+    // Preview the changes
+    for step in &steps {
+        println!(
+            "\x1b[1;32m=== {} ({}) ===\x1b[0m\n{}\n",
+            step.data.id,
+            match step.target_code {
+                Some(_) => "update",
+                None => "create",
+            },
+            diff::line_diff(
+                step.target_code.as_deref().unwrap_or_default(),
+                &step.data.code
+            ),
+        );
+    }
+
+    // Lint every script on the target before any modification takes place
+    for step in &steps {
+        debug!("Linting script `{}` on `{}`...", step.data.id, options.to);
+        let response = target
+            .exec_homescript_code(
+                &step.data.code,
+                vec![],
+                HmsRunMode::Lint {
+                    module_name: step.data.id.as_str(),
+                    is_driver: step.data.type_ == HomescriptType::Driver,
+                },
+            )
+            .await?;
+        match response.success {
+            true => debug!("Linting of `{}` discovered no problems", step.data.id),
+            false if !options.force => {
+                return Err(Error::LintErrors {
+                    errors: response.errors,
+                    code: step.data.code.clone(),
+                    file_contents: response.file_contents,
+                })
+            }
+            false => warn!(
+                "Linting of `{}` discovered errors: force-promoting to `{}`",
+                step.data.id, options.to
+            ),
+        }
+    }
+
+    if options.dry_run {
+        info!("Dry run: no changes were made to `{}`", options.to);
+        return Ok(());
+    }
+
+    if !options.yes
+        && !confirm(&format!(
+            "Promote {} script(s) to `{}`?",
+            steps.len(),
+            options.to
+        ))?
+    {
+        info!("Aborted.");
+        return Ok(());
+    }
+
+    for step in steps {
+        let id = step.data.id.clone();
+        match step.target_code {
+            Some(_) => target.modify_homescript(&step.data).await?,
+            None => target.create_homescript(&step.data).await?,
+        }
+        info!("Successfully promoted script `{id}` to `{}`", options.to);
+    }
+    Ok(())
+}
+
+/// Creates a client for the server profile with the given ID
+async fn connect(config: &Config, server_id: &str, version_check: bool) -> Result<Client> {
+    let profile = match config.servers.iter().find(|server| server.id == server_id) {
+        Some(profile) => profile,
+        None => return Err(Error::InvalidServer(server_id.to_string())),
+    };
+    // The token from the environment only applies to the server selected on the command line
+    let auth = profile.auth(false).map_err(Error::Credentials)?;
+    debug!("Connecting to server `{server_id}`...");
+    profile
+        .connect(auth, version_check)
+        .await
+        .map_err(|err| Error::Connect {
+            server_id: server_id.to_string(),
+            err,
+        })
+}
+
+/// Picks the scripts with the given IDs, fails if any of them does not exist
+fn select_scripts(scripts: Vec<Homescript>, ids: &[String]) -> Result<Vec<HomescriptData>> {
+    if let Some(missing) = ids
+        .iter()
+        .find(|id| !scripts.iter().any(|script| script.data.id == **id))
+    {
+        return Err(Error::ScriptDoesNotExist(missing.to_string()));
+    }
+    Ok(scripts
+        .into_iter()
+        .filter(|script| ids.contains(&script.data.id))
+        .map(|script| script.data)
+        .collect())
+}
+
+fn is_same_data(left: &HomescriptData, right: &HomescriptData) -> bool {
+    left.code == right.code
+        && left.name == right.name
+        && left.description == right.description
+        && left.workspace == right.workspace
+        && left.md_icon == right.md_icon
+        && left.quick_actions_enabled == right.quick_actions_enabled
+        && left.scheduler_enabled == right.scheduler_enabled
+        && left.is_widget == right.is_widget
+        && left.type_ == right.type_
+}
+
+/// Asks the user a yes / no question on the terminal
+fn confirm(question: &str) -> Result<bool> {
+    print!("{question} [y/N] ");
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
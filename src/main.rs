@@ -5,13 +5,16 @@ use cli::{Args, Command};
 use log::{error, info, Level};
 use loggerv::Logger;
 use reqwest::StatusCode;
-use smarthome_sdk_rs::{Auth, Client, User};
 
 mod admin;
+mod automation;
 mod cli;
 mod config;
+mod device;
 mod hms;
+mod metrics;
 mod power;
+mod schedule;
 
 #[tokio::main]
 async fn main() {
@@ -45,7 +48,7 @@ async fn main() {
     }
 
     // Read or create the configuration file
-    let conf = match config::read_config(&config_path) {
+    let conf = match config::read_config(&config_path, args.server.as_deref()) {
         Ok(conf) => match conf {
             Some(conf) => conf,
             None => {
@@ -54,14 +57,7 @@ async fn main() {
             }
         },
         Err(err) => {
-            error!(
-                "Could not read nor create config file (at {config_path}): {}",
-                match err {
-                    config::Error::IO(err) => format!("IO error: {err}"),
-                    config::Error::Parse(err) => format!("invalid TOML syntax: {err}"),
-                    config::Error::Validate(err) => format!("Validation failed: {err}"),
-                }
-            );
+            error!("Could not read nor create config file (at {config_path}): {err}");
             process::exit(1);
         }
     };
@@ -78,20 +74,14 @@ async fn main() {
         None => &conf.servers[0],
     };
 
+    // Resolve the credentials, the token of the selected server may also be set using the environment
+    let auth = profile.auth(true).unwrap_or_else(|err| {
+        error!("{err}");
+        process::exit(1);
+    });
+
     // Create a Smarthome client
-    let client = match Client::new(
-        &profile.url,
-        match profile.token.is_empty() {
-            true => Auth::QueryPassword(User {
-                username: profile.username.clone(),
-                password: profile.password.clone(),
-            }),
-            false => Auth::QueryToken(profile.token.clone()),
-        },
-        !args.no_version_check,
-    )
-    .await
-    {
+    let client = match profile.connect(auth, !args.no_version_check).await {
         Ok(client) => client,
         Err(err) => {
             error!(
@@ -114,13 +104,31 @@ async fn main() {
     };
 
     match args.subcommand {
-        Command::Power(sub) => power::handle_subcommand(sub, &client, &conf)
+        Command::Power(sub) => power::handle_subcommand(sub, &client, &conf, &config_path)
             .await
             .unwrap_or_else(|err| {
                 error!("{err}");
                 process::exit(1);
             }),
-        Command::Hms(sub) => hms::handle_subcommand(sub, &client, &conf)
+        Command::Device(sub) => device::handle_subcommand(sub, &client)
+            .await
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                process::exit(1);
+            }),
+        Command::Hms(sub) => hms::handle_subcommand(sub, &client, &conf, !args.no_version_check)
+            .await
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                process::exit(1);
+            }),
+        Command::Automation(sub) => automation::handle_subcommand(sub, &client)
+            .await
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                process::exit(1);
+            }),
+        Command::Schedule(sub) => schedule::handle_subcommand(sub, &client)
             .await
             .unwrap_or_else(|err| {
                 error!("{err}");
@@ -132,6 +140,12 @@ async fn main() {
                 error!("{err}");
                 process::exit(1);
             }),
+        Command::ServeMetrics { listen, interval } => metrics::serve(&client, listen, interval.0)
+            .await
+            .unwrap_or_else(|err| {
+                error!("{err}");
+                process::exit(1);
+            }),
         Command::Config => unreachable!("Config should have been covered before"),
     };
 }
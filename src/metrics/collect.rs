@@ -0,0 +1,264 @@
+use std::fmt::Write;
+
+use chrono::Utc;
+use log::{debug, warn};
+use smarthome_sdk_rs::{Client, DeviceCapability, HydratedDeviceResponse};
+
+use crate::power::{ParsedDevice, Recorder};
+
+/// A metric family in the Prometheus text format
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    samples: Vec<(String, f64)>,
+}
+
+impl Family {
+    fn new(name: &'static str, help: &'static str) -> Self {
+        Self {
+            name,
+            help,
+            samples: vec![],
+        }
+    }
+
+    fn add(&mut self, labels: &[(&str, &str)], value: f64) {
+        let labels = labels
+            .iter()
+            .map(|(key, value)| format!("{key}=\"{}\"", escape(value)))
+            .collect::<Vec<String>>()
+            .join(",");
+        self.samples.push((labels, value));
+    }
+
+    fn write(&self, out: &mut String) {
+        writeln!(out, "# HELP {} {}", self.name, self.help)
+            .expect("Writing to a string cannot fail");
+        writeln!(out, "# TYPE {} gauge", self.name).expect("Writing to a string cannot fail");
+        for (labels, value) in &self.samples {
+            match labels.is_empty() {
+                true => writeln!(out, "{} {value}", self.name),
+                false => writeln!(out, "{}{{{labels}}} {value}", self.name),
+            }
+            .expect("Writing to a string cannot fail");
+        }
+    }
+}
+
+/// Escapes a label value as required by the Prometheus text format
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+/// Polls the server and renders all metrics in the Prometheus text format
+/// Sources which cannot be fetched are omitted and reported through `smarthome_scrape_success`
+pub async fn collect(client: &Client, recorder: &mut Recorder) -> String {
+    debug!("Polling metrics...");
+    let (switches, usage, debug_info) = tokio::join!(
+        client.all_switches(),
+        client.power_usage(false),
+        client.debug_info()
+    );
+
+    let mut success = Family::new(
+        "smarthome_scrape_success",
+        "Whether the source could be fetched during the last poll",
+    );
+    let mut families = vec![];
+
+    match switches {
+        Ok(switches) => {
+            success.add(&[("source", "switches")], 1.0);
+            families.extend(switch_families(&switches));
+            let devices: Vec<ParsedDevice> = switches.into_iter().map(ParsedDevice::from).collect();
+            if let Err(err) = recorder.record(client, &devices) {
+                warn!("Could not record device states: {err}");
+            }
+        }
+        Err(err) => {
+            warn!("Could not fetch switches: {err}");
+            success.add(&[("source", "switches")], 0.0);
+        }
+    }
+
+    match usage {
+        Ok(usage) => {
+            success.add(&[("source", "power_usage")], 1.0);
+            if let Some(latest) = usage.last() {
+                let mut on = Family::new(
+                    "smarthome_power_usage_on_watts",
+                    "Power drawn by switches which are turned on, as of the latest power draw point",
+                );
+                on.add(&[], latest.on.watts as f64);
+                let mut off = Family::new(
+                    "smarthome_power_usage_off_watts",
+                    "Power which switches that are turned off would draw, as of the latest power draw point",
+                );
+                off.add(&[], latest.off.watts as f64);
+                let mut time = Family::new(
+                    "smarthome_power_usage_timestamp_seconds",
+                    "Unix time of the latest power draw point",
+                );
+                time.add(&[], latest.time as f64 / 1000.0);
+                families.extend([on, off, time]);
+            }
+        }
+        Err(err) => {
+            warn!("Could not fetch power usage: {err}");
+            success.add(&[("source", "power_usage")], 0.0);
+        }
+    }
+
+    match debug_info {
+        Ok(debug_info) => {
+            success.add(&[("source", "debug_info")], 1.0);
+            let mut online = Family::new(
+                "smarthome_hardware_node_online",
+                "Whether the hardware node is online",
+            );
+            let mut enabled = Family::new(
+                "smarthome_hardware_node_enabled",
+                "Whether the hardware node is enabled",
+            );
+            for node in &debug_info.hardware_nodes {
+                let labels = [("name", node.name.as_str()), ("url", node.url.as_str())];
+                online.add(&labels, f64::from(u8::from(node.online)));
+                enabled.add(&labels, f64::from(u8::from(node.enabled)));
+            }
+            families.extend([online, enabled]);
+        }
+        Err(err) => {
+            warn!("Could not fetch debug information: {err}");
+            success.add(&[("source", "debug_info")], 0.0);
+        }
+    }
+
+    let mut timestamp = Family::new(
+        "smarthome_last_poll_timestamp_seconds",
+        "Unix time of the last poll",
+    );
+    timestamp.add(&[], Utc::now().timestamp() as f64);
+    families.extend([success, timestamp]);
+
+    let mut out = String::new();
+    for family in families {
+        family.write(&mut out);
+    }
+    out
+}
+
+fn switch_families(switches: &[HydratedDeviceResponse]) -> Vec<Family> {
+    let mut power_on = Family::new(
+        "smarthome_switch_power_on",
+        "Whether the switch is turned on",
+    );
+    let mut watts = Family::new(
+        "smarthome_switch_watts",
+        "Power which the switch draws while it is turned on",
+    );
+    let mut draw = Family::new(
+        "smarthome_switch_draw_watts",
+        "Power which the switch currently draws",
+    );
+    let (mut active, mut passive) = (0, 0);
+
+    for switch in switches {
+        if !switch
+            .extractions
+            .config
+            .capabilities
+            .contains(&DeviceCapability::Power)
+        {
+            continue;
+        }
+        let Some(power) = &switch.extractions.power_information else {
+            continue;
+        };
+        let labels = [
+            ("id", switch.shallow.id.as_str()),
+            ("name", switch.shallow.name.as_str()),
+            ("room", switch.shallow.room_id.as_str()),
+        ];
+        power_on.add(&labels, f64::from(u8::from(power.state)));
+        watts.add(&labels, power.power_draw_watts as f64);
+        match power.state {
+            true => {
+                draw.add(&labels, power.power_draw_watts as f64);
+                active += power.power_draw_watts;
+            }
+            false => {
+                draw.add(&labels, 0.0);
+                passive += power.power_draw_watts;
+            }
+        }
+    }
+
+    let mut active_family = Family::new(
+        "smarthome_power_active_watts",
+        "Power drawn by all switches which are turned on",
+    );
+    active_family.add(&[], active as f64);
+    let mut passive_family = Family::new(
+        "smarthome_power_passive_watts",
+        "Power which all switches that are turned off would draw",
+    );
+    passive_family.add(&[], passive as f64);
+    let mut total_family = Family::new(
+        "smarthome_power_total_watts",
+        "Power which all switches would draw if they were turned on",
+    );
+    total_family.add(&[], (active + passive) as f64);
+
+    vec![
+        power_on,
+        watts,
+        draw,
+        active_family,
+        passive_family,
+        total_family,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn label_values_are_escaped() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape(r"back\slash"), r"back\\slash");
+        assert_eq!(escape("\"quoted\""), r#"\"quoted\""#);
+        assert_eq!(escape("two\nlines"), r"two\nlines");
+    }
+
+    #[test]
+    fn family_is_written_in_the_text_exposition_format() {
+        let mut family = Family::new(
+            "smarthome_switch_power_on",
+            "Whether the switch is turned on",
+        );
+        family.add(&[("id", "lamp"), ("name", "Desk \"lamp\"\nC:\\")], 1.0);
+        family.add(&[("id", "fan"), ("name", "Fan")], 0.0);
+        let mut unlabeled = Family::new("smarthome_power_total_watts", "Total power");
+        unlabeled.add(&[], 42.0);
+
+        let mut out = String::new();
+        family.write(&mut out);
+        unlabeled.write(&mut out);
+        assert_eq!(
+            out,
+            concat!(
+                "# HELP smarthome_switch_power_on Whether the switch is turned on\n",
+                "# TYPE smarthome_switch_power_on gauge\n",
+                "smarthome_switch_power_on{id=\"lamp\",name=\"Desk \\\"lamp\\\"\\nC:\\\\\"} 1\n",
+                "smarthome_switch_power_on{id=\"fan\",name=\"Fan\"} 0\n",
+                "# HELP smarthome_power_total_watts Total power\n",
+                "# TYPE smarthome_power_total_watts gauge\n",
+                "smarthome_power_total_watts 42\n",
+            )
+        );
+    }
+}
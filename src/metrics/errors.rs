@@ -0,0 +1,19 @@
+use std::{fmt::Display, io, net::SocketAddr};
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub enum Error {
+    Bind { address: SocketAddr, err: io::Error },
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::Bind { address, err } => format!("Could not listen on `{address}`: {err}"),
+            }
+        )
+    }
+}
@@ -0,0 +1,215 @@
+use std::{io, net::SocketAddr, sync::Arc, time::Duration};
+
+use log::{debug, info, warn};
+use smarthome_sdk_rs::Client;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::TcpListener,
+    sync::watch,
+    time::{self, MissedTickBehavior},
+};
+
+use crate::power::Recorder;
+use errors::{Error, Result};
+
+mod collect;
+mod errors;
+
+/// Polls the server periodically and serves the collected metrics on `/metrics` until interrupted
+pub async fn serve(client: &Client, address: SocketAddr, interval: Duration) -> Result<()> {
+    let listener = TcpListener::bind(address)
+        .await
+        .map_err(|err| Error::Bind { address, err })?;
+    info!("Serving metrics on `http://{address}/metrics`, polling every {interval:?}");
+
+    // Polling runs concurrently to accepting connections, so a slow server does not block the responses
+    // Until the first poll completes, empty metrics are served
+    let (sender, receiver) = watch::channel(Arc::new(String::new()));
+    let poll = async {
+        let mut recorder = Recorder::default();
+        let mut ticker = time::interval(interval);
+        ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            // The first tick completes immediately, so the metrics are available right away
+            ticker.tick().await;
+            sender.send_replace(Arc::new(collect::collect(client, &mut recorder).await));
+        }
+    };
+    let accept = async {
+        loop {
+            match listener.accept().await {
+                Ok((stream, peer)) => {
+                    debug!("Accepted connection from `{peer}`");
+                    let metrics = Arc::clone(&receiver.borrow());
+                    tokio::spawn(async move {
+                        if let Err(err) = respond(stream, &metrics).await {
+                            debug!("Could not respond to `{peer}`: {err}");
+                        }
+                    });
+                }
+                Err(err) => warn!("Could not accept connection: {err}"),
+            }
+        }
+    };
+
+    tokio::select! {
+        _ = poll => unreachable!("Polling never stops"),
+        _ = accept => unreachable!("Accepting connections never stops"),
+        _ = tokio::signal::ctrl_c() => {
+            info!("Shutting down...");
+            Ok(())
+        }
+    }
+}
+
+/// Upper bound for the size of a request's head, larger requests are rejected
+const MAX_REQUEST_HEAD: usize = 8192;
+
+/// Answers a single HTTP request, only `GET /metrics` is served
+async fn respond<S: AsyncRead + AsyncWrite + Unpin>(
+    mut stream: S,
+    metrics: &str,
+) -> io::Result<()> {
+    let (status, content_type, body) = match read_head(&mut stream).await? {
+        Head::Complete(head) => route(&String::from_utf8_lossy(&head), metrics),
+        Head::TooLarge => (
+            "431 Request Header Fields Too Large",
+            "text/plain; charset=utf-8",
+            "Request header fields too large\n",
+        ),
+        Head::Incomplete => (
+            "400 Bad Request",
+            "text/plain; charset=utf-8",
+            "Bad request\n",
+        ),
+    };
+
+    stream
+        .write_all(
+            format!(
+                "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .as_bytes(),
+        )
+        .await?;
+    stream.shutdown().await
+}
+
+enum Head {
+    Complete(Vec<u8>),
+    TooLarge,
+    /// The connection was closed before the head was terminated
+    Incomplete,
+}
+
+/// Reads the request line and headers, which are terminated by an empty line
+async fn read_head<S: AsyncRead + Unpin>(stream: &mut S) -> io::Result<Head> {
+    let mut head = Vec::new();
+    let mut buffer = [0; 1024];
+    loop {
+        let read = stream.read(&mut buffer).await?;
+        if read == 0 {
+            return Ok(Head::Incomplete);
+        }
+        // The terminator may span two reads, so the search starts slightly before the new data
+        let search_from = head.len().saturating_sub(3);
+        head.extend_from_slice(&buffer[..read]);
+        if let Some(end) = head[search_from..]
+            .windows(4)
+            .position(|window| window == b"\r\n\r\n")
+        {
+            head.truncate(search_from + end);
+            return Ok(Head::Complete(head));
+        }
+        if head.len() > MAX_REQUEST_HEAD {
+            return Ok(Head::TooLarge);
+        }
+    }
+}
+
+/// Selects status, content type and body of the response based on the request line
+fn route<'a>(head: &str, metrics: &'a str) -> (&'static str, &'static str, &'a str) {
+    let mut request_line = head.lines().next().unwrap_or_default().split(' ');
+    match (request_line.next(), request_line.next()) {
+        (Some("GET"), Some("/metrics")) => (
+            "200 OK",
+            "text/plain; version=0.0.4; charset=utf-8",
+            metrics,
+        ),
+        (Some("GET"), Some("/")) => (
+            "200 OK",
+            "text/plain; charset=utf-8",
+            "Smarthome metrics are served on /metrics\n",
+        ),
+        (Some("GET"), _) => ("404 Not Found", "text/plain; charset=utf-8", "Not found\n"),
+        _ => (
+            "405 Method Not Allowed",
+            "text/plain; charset=utf-8",
+            "Method not allowed\n",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::io::duplex;
+
+    use super::*;
+
+    /// Sends the request in the given chunks and returns the complete response
+    async fn exchange(chunks: &[&[u8]], metrics: &str) -> String {
+        let (mut client, server) = duplex(64 * 1024);
+        for chunk in chunks {
+            client.write_all(chunk).await.unwrap();
+        }
+        respond(server, metrics).await.unwrap();
+        let mut response = String::new();
+        client.read_to_string(&mut response).await.unwrap();
+        response
+    }
+
+    #[tokio::test]
+    async fn metrics_are_served_in_the_text_exposition_format() {
+        let metrics = "# HELP up Whether the exporter is up\n# TYPE up gauge\nup 1\n";
+        let response = exchange(
+            &[b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n"],
+            metrics,
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.contains("\r\nContent-Type: text/plain; version=0.0.4; charset=utf-8\r\n"));
+        assert!(response.contains(&format!("\r\nContent-Length: {}\r\n", metrics.len())));
+        assert!(response.ends_with(&format!("\r\n\r\n{metrics}")));
+    }
+
+    #[tokio::test]
+    async fn head_split_across_reads_is_routed() {
+        let response = exchange(
+            &[
+                b"GET /metr",
+                b"ics HTTP/1.1\r\nHost: localhost\r",
+                b"\n\r\n",
+            ],
+            "up 1\n",
+        )
+        .await;
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+        assert!(response.ends_with("\r\n\r\nup 1\n"));
+    }
+
+    #[tokio::test]
+    async fn other_requests_are_not_served() {
+        let response = exchange(&[b"GET /other HTTP/1.1\r\n\r\n"], "").await;
+        assert!(response.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        let response = exchange(&[b"POST /metrics HTTP/1.1\r\n\r\n"], "").await;
+        assert!(response.starts_with("HTTP/1.1 405 Method Not Allowed\r\n"));
+    }
+
+    #[tokio::test]
+    async fn oversized_heads_are_rejected() {
+        let header = format!("X-Filler: {}\r\n", "a".repeat(MAX_REQUEST_HEAD));
+        let response = exchange(&[b"GET /metrics HTTP/1.1\r\n", header.as_bytes()], "up 1\n").await;
+        assert!(response.starts_with("HTTP/1.1 431 Request Header Fields Too Large\r\n"));
+    }
+}
@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+
+use smarthome_sdk_rs::Client;
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    errors::{Error, Result},
+    samples::{self, Sample},
+    stats::Timespan,
+    tariff,
+};
+use crate::config::PowerConfig;
+
+/// Consecutive samples which are further apart are not used, the device states in between are unknown
+const MAX_SAMPLE_GAP_MS: i64 = 15 * 60 * 1000;
+
+#[derive(Default)]
+struct DeviceEnergy {
+    name: String,
+    watts: usize,
+    on_ms: i64,
+    kwh: f64,
+    cost: f64,
+}
+
+#[derive(Tabled)]
+struct TableDeviceEnergy {
+    #[tabled(rename = "#")]
+    rank: usize,
+    #[tabled(rename = "ID")]
+    id: String,
+    #[tabled(rename = "Name")]
+    name: String,
+    #[tabled(rename = "Watts")]
+    watts: usize,
+    #[tabled(rename = "On (h)")]
+    on_hours: String,
+    #[tabled(rename = "Used (kWh)")]
+    kwh: String,
+    #[tabled(rename = "Cost")]
+    cost: String,
+    #[tabled(rename = "Share")]
+    share: String,
+}
+
+/// Estimates the energy used by each device by combining its power draw with the intervals in which it was turned on
+/// The intervals are derived from the device states which are recorded locally while polling
+pub fn show(client: &Client, config: &PowerConfig, timespan: Timespan) -> Result<()> {
+    let since = timespan.since.timestamp_millis();
+    let until = timespan.until.timestamp_millis();
+    if since >= until {
+        return Err(Error::InvalidTimespan);
+    }
+    let samples = samples::read(client)?;
+
+    let mut devices: HashMap<String, DeviceEnergy> = HashMap::new();
+    let mut covered_ms = 0;
+    for window in samples.windows(2) {
+        let (sample, next): (&Sample, &Sample) = (&window[0], &window[1]);
+        let (start, end) = (sample.time.max(since), next.time.min(until));
+        if end <= start || next.time - sample.time > MAX_SAMPLE_GAP_MS {
+            continue;
+        }
+        covered_ms += end - start;
+
+        // The states of a sample are assumed to hold until the next sample
+        let price = tariff::price_at(config, (start + (end - start) / 2) as u64);
+        for device in &sample.devices {
+            let entry = devices.entry(device.id.clone()).or_default();
+            entry.name.clone_from(&device.name);
+            entry.watts = device.watts;
+            if device.power_on {
+                let kwh =
+                    device.watts as f64 * (end - start) as f64 / (1000.0 * 60.0 * 60.0 * 1000.0);
+                entry.on_ms += end - start;
+                entry.kwh += kwh;
+                entry.cost += kwh * price;
+            }
+        }
+    }
+
+    if covered_ms == 0 {
+        return Err(Error::NoDeviceSamples);
+    }
+
+    let total_kwh: f64 = devices.values().map(|device| device.kwh).sum();
+    let total_cost: f64 = devices.values().map(|device| device.cost).sum();
+    let mut ranked: Vec<(String, DeviceEnergy)> = devices.into_iter().collect();
+    ranked.sort_by(|(left_id, left), (right_id, right)| {
+        right
+            .kwh
+            .total_cmp(&left.kwh)
+            .then_with(|| left_id.cmp(right_id))
+    });
+
+    let mut table =
+        Table::new(
+            ranked
+                .into_iter()
+                .enumerate()
+                .map(|(index, (id, device))| TableDeviceEnergy {
+                    rank: index + 1,
+                    id,
+                    name: device.name,
+                    watts: device.watts,
+                    on_hours: format!("{:.1}", device.on_ms as f64 / (1000.0 * 60.0 * 60.0)),
+                    kwh: format!("{:.3}", device.kwh),
+                    cost: format!("{:.2} {}", device.cost, config.currency),
+                    share: match total_kwh > 0.0 {
+                        true => format!("{:.1} %", device.kwh * 100.0 / total_kwh),
+                        false => "-".to_string(),
+                    },
+                }),
+        );
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+
+    let span_ms = until - since;
+    println!(
+        "  Total     {total_kwh:.2} KWh ({total_cost:.2} {})
+  Coverage  {:.1} h of {:.1} h ({:.0} %)",
+        config.currency,
+        covered_ms as f64 / (1000.0 * 60.0 * 60.0),
+        span_ms as f64 / (1000.0 * 60.0 * 60.0),
+        covered_ms as f64 * 100.0 / span_ms as f64,
+    );
+    Ok(())
+}
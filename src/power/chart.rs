@@ -0,0 +1,146 @@
+use chrono::{Local, TimeZone};
+use crossterm::terminal;
+use smarthome_sdk_rs::PowerDrawPoint;
+
+/// The height of the chart in rows, excluding the axis
+const CHART_HEIGHT: usize = 12;
+/// Used if the width of the terminal cannot be determined
+const DEFAULT_WIDTH: usize = 80;
+/// The width of the y-axis labels, including the axis itself
+const Y_LABEL_WIDTH: usize = 9;
+/// The minimum distance between the start of two x-axis labels
+const X_LABEL_SPACING: usize = 14;
+/// Partial blocks from one eighth to a full block
+const BLOCKS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// The average on- and off-watts of all points which fall into a column, `None` if there are none
+type Column = Option<(f64, f64)>;
+
+/// Prints the power draw history as a chart which fits the width of the terminal
+/// On-watts are drawn as green bars, off-watts as a red line
+pub fn print(data: &[PowerDrawPoint]) {
+    let width = terminal::size().map_or(DEFAULT_WIDTH, |(columns, _)| columns as usize);
+    print!("{}", render(data, width));
+}
+
+fn render(data: &[PowerDrawPoint], width: usize) -> String {
+    let (Some(first), Some(last)) = (data.first(), data.last()) else {
+        return "  No power draw data to chart\n".to_string();
+    };
+    let columns = columns(
+        data,
+        first.time,
+        last.time,
+        width.saturating_sub(Y_LABEL_WIDTH).max(1),
+    );
+
+    let max = columns
+        .iter()
+        .flatten()
+        .map(|(on, off)| on.max(*off))
+        .fold(0.0, f64::max)
+        .max(1.0);
+    // Heights are measured in eighths of a row
+    let scale = |watts: f64| (watts / max * (CHART_HEIGHT * 8) as f64).round() as usize;
+
+    let mut chart = String::new();
+    for row in (0..CHART_HEIGHT).rev() {
+        let label = match row {
+            _ if row == CHART_HEIGHT - 1 => format!("{max:>6.0} W"),
+            _ if row == CHART_HEIGHT / 2 => format!("{:>6.0} W", max / 2.0),
+            _ => String::new(),
+        };
+        chart.push_str(&format!("{label:>8}┤"));
+        for column in &columns {
+            let Some((on, off)) = column else {
+                chart.push(' ');
+                continue;
+            };
+            let (on, off) = (scale(*on), scale(*off));
+            // The off-line is drawn in the row which contains its value and takes precedence over the bars
+            // Nothing is drawn if no switch is turned off, so that the bottom of the bars stays visible
+            if off > 0 && (off / 8 == row || off == CHART_HEIGHT * 8 && row == CHART_HEIGHT - 1) {
+                chart.push_str("\x1b[31m•\x1b[0m");
+                continue;
+            }
+            let filled = on.saturating_sub(row * 8).min(8);
+            match filled {
+                0 => chart.push(' '),
+                filled => chart.push_str(&format!("\x1b[32m{}\x1b[0m", BLOCKS[filled - 1])),
+            }
+        }
+        chart.push('\n');
+    }
+
+    // The x-axis with labels in local time
+    chart.push_str(&format!("{:>8}└{}\n", "0 W", "─".repeat(columns.len())));
+    let span = last.time.saturating_sub(first.time);
+    let format = match span > 2 * 24 * 60 * 60 * 1000 {
+        true => "%m-%d",
+        false => "%H:%M",
+    };
+    let mut labels = " ".repeat(Y_LABEL_WIDTH);
+    let mut column = 0;
+    while column + 5 <= columns.len() {
+        let time = first.time + span * column as u64 / columns.len().max(1) as u64;
+        let label = match Local.timestamp_millis_opt(time as i64).single() {
+            Some(time) => time.format(format).to_string(),
+            None => String::new(),
+        };
+        labels.push_str(&format!("{label:<X_LABEL_SPACING$}"));
+        column += X_LABEL_SPACING;
+    }
+    chart.push_str(labels.trim_end());
+    chart.push_str("\n\n");
+    chart.push_str(&format!(
+        "{}\x1b[32m█\x1b[0m on  \x1b[31m•\x1b[0m off\n",
+        " ".repeat(Y_LABEL_WIDTH)
+    ));
+    chart
+}
+
+/// Distributes the points over the columns by their time and averages the points of each column
+pub(super) fn columns(data: &[PowerDrawPoint], start: u64, end: u64, count: usize) -> Vec<Column> {
+    let span = end.saturating_sub(start).max(1);
+    let mut sums = vec![(0.0, 0.0, 0); count];
+    for point in data {
+        let index = ((point.time.saturating_sub(start)) as u128 * count as u128 / span as u128)
+            .min(count as u128 - 1) as usize;
+        sums[index].0 += point.on.watts as f64;
+        sums[index].1 += point.off.watts as f64;
+        sums[index].2 += 1;
+    }
+    sums.into_iter()
+        .map(|(on, off, count)| match count {
+            0 => None,
+            count => Some((on / count as f64, off / count as f64)),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::fixtures::point;
+
+    /// The chart rows from top to bottom, without the axis and the legend
+    fn rows(chart: &str) -> Vec<&str> {
+        chart.lines().take(CHART_HEIGHT).collect()
+    }
+
+    #[test]
+    fn no_off_marker_without_off_watts() {
+        let chart = render(&[point(0, 500, 0), point(60_000, 500, 0)], 40);
+        assert!(rows(&chart).iter().all(|row| !row.contains('•')));
+        assert!(rows(&chart)[CHART_HEIGHT - 1].contains('█'));
+    }
+
+    #[test]
+    fn off_marker_in_the_row_of_its_value() {
+        let chart = render(&[point(0, 1000, 500), point(60_000, 1000, 500)], 40);
+        let rows = rows(&chart);
+        assert!(rows[CHART_HEIGHT / 2 - 1].contains('•'));
+        assert!(rows[CHART_HEIGHT - 1].contains('█'));
+        assert!(!rows[CHART_HEIGHT - 1].contains('•'));
+    }
+}
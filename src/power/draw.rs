@@ -1,13 +1,19 @@
-use std::usize;
-
-use super::errors::{Error, Result};
-use crate::config::PowerConfig;
-use smarthome_sdk_rs::{Client, DeviceCapability, HydratedDeviceResponse, PowerDrawPoint};
+use super::{
+    chart,
+    errors::{Error, Result},
+    samples,
+    stats::{self, Gap, Timespan},
+    tariff,
+};
+use crate::{cli::Resolution, config::PowerConfig};
+use log::warn;
+use smarthome_sdk_rs::{Client, DeviceCapability, HydratedDeviceResponse};
 use tabled::{
     settings::{format::Format, object::Rows, Modify, Style},
     Table, Tabled,
 };
 
+#[derive(Clone)]
 pub struct ParsedDevice {
     pub id: String,
     pub name: String,
@@ -15,6 +21,7 @@ pub struct ParsedDevice {
     pub power: Option<ParsedPower>,
 }
 
+#[derive(Clone)]
 pub struct ParsedPower {
     pub status: bool,
     pub watts: usize,
@@ -96,112 +103,237 @@ impl From<ParsedDevice> for TableDevice {
     }
 }
 
+/// The power which is currently drawn by all devices that report their power draw
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct CurrentDraw {
+    /// Watts drawn by devices which are turned on
+    pub active: usize,
+    /// Watts which devices that are turned off would draw
+    pub passive: usize,
+}
+
+impl CurrentDraw {
+    pub fn total(&self) -> usize {
+        self.active + self.passive
+    }
+
+    /// Returns the share of the watts in the total power draw, `None` if there is no power draw at all
+    fn share(&self, watts: usize) -> Option<f64> {
+        match self.total() {
+            0 => None,
+            total => Some(watts as f64 * 100.0 / total as f64),
+        }
+    }
+}
+
+impl From<&[ParsedDevice]> for CurrentDraw {
+    fn from(devices: &[ParsedDevice]) -> Self {
+        devices
+            .iter()
+            .filter_map(|device| device.power.as_ref())
+            .fold(Self::default(), |mut draw, power| {
+                match power.status {
+                    true => draw.active += power.watts,
+                    false => draw.passive += power.watts,
+                }
+                draw
+            })
+    }
+}
+
+fn display_share(share: Option<f64>) -> String {
+    match share {
+        Some(share) => format!("{share:>3.0} %"),
+        None => "n/a".to_string(),
+    }
+}
+
+/// Prints the current power draw and statistics about the given timespan
+/// If no timespan is given, the last 24 hours are analyzed
+/// Both sections are independent of each other: if one cannot be computed, it is shown as `n/a`
 pub async fn power_draw(
     client: &Client,
     config: &PowerConfig,
     use_simple_display: bool,
+    timespan: Option<Timespan>,
+    resolution: Option<Resolution>,
+    show_chart: bool,
 ) -> Result<()> {
-    let switches = match client.all_switches().await {
-        Ok(response) => response,
-        Err(err) => return Err(Error::GetDevices(err)),
+    let title = match &timespan {
+        Some(timespan) => format!(
+            "Metrics {} - {}",
+            timespan.since.format("%Y-%m-%d %H:%M"),
+            timespan.until.format("%Y-%m-%d %H:%M")
+        ),
+        None => "24-Hour Metrics   ".to_string(),
     };
+    let timespan = timespan.unwrap_or_else(Timespan::last_day);
+    if timespan.since >= timespan.until {
+        return Err(Error::InvalidTimespan);
+    }
 
-    let (all, active): (Vec<u32>, Vec<u32>) = switches
-        .clone()
-        .into_iter()
-        .map(|switch| {
-            let switch_alt = ParsedDevice::from(switch);
-
-            match switch_alt.power {
-                Some(power) => (
-                    power.watts as u32,
-                    if power.status {
-                        power.watts as u32
-                    } else {
-                        0u32
-                    },
-                ),
-                None => (0u32, 0u32),
+    let devices = client
+        .all_switches()
+        .await
+        .map_err(Error::GetDevices)
+        .map(|switches| {
+            switches
+                .into_iter()
+                .map(ParsedDevice::from)
+                .collect::<Vec<ParsedDevice>>()
+        });
+    let historic_data = stats::history(client, &timespan).await;
+    // There is nothing to show if neither section is available
+    let (devices, historic_data) = match (devices, historic_data) {
+        (Err(err), Err(_)) => return Err(err),
+        sections => sections,
+    };
+
+    match devices {
+        Ok(devices) => {
+            // Every snapshot improves the per-device energy estimates, but it is not essential here
+            if let Err(err) = samples::record(client, &devices) {
+                warn!("Could not record device states: {err}");
             }
-        })
-        .unzip();
+            let draw = CurrentDraw::from(devices.as_slice());
 
-    let power_total = all.into_iter().sum::<u32>();
-    let power_active = active.into_iter().sum::<u32>();
-    let power_passive = power_total - power_active;
+            // Only print the table if the simple display is turned off
+            if !use_simple_display {
+                let mut table = Table::new(
+                    devices
+                        .into_iter()
+                        .map(TableDevice::from)
+                        .collect::<Vec<TableDevice>>(),
+                );
+                table.with(Style::modern().remove_horizontal()).with(
+                    Modify::new(Rows::first())
+                        .with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m"))),
+                );
+                println!("{}", table);
+            }
 
-    let historic_data = match client.power_usage(false).await {
-        Ok(response) => response,
-        Err(err) => return Err(Error::GetPowerDrawData(err)),
-    };
+            println!(
+                "=== Current Power Draw ===
+  Active  \x1b[1;32m*\x1b[1;0m {:>4} W ({})
+  Passive \x1b[1;31m.\x1b[1;0m {:>4} W ({})
+  Total   Σ {:>4} W (100 %)",
+                draw.active,
+                display_share(draw.share(draw.active)),
+                draw.passive,
+                display_share(draw.share(draw.passive)),
+                draw.total(),
+            );
+            if draw.total() == 0 {
+                println!("  \x1b[1;30mn/a\x1b[1;0m       No device reports its power draw");
+            }
+        }
+        Err(err) => println!("=== Current Power Draw ===\n  \x1b[1;30mn/a\x1b[1;0m       {err}"),
+    }
 
-    let kwh_24_hours = kwh_total(&historic_data)?;
-    let peak_24_hours = match historic_data
-        .iter()
-        .max_by_key(|measurement| measurement.on.watts)
-    {
-        Some(max) => max.on.watts,
-        None => return Err(Error::NotEnoughPowerDrawData),
+    println!("\n=== {title} ===");
+    let historic_data = match historic_data {
+        Ok(data) => data,
+        Err(err) => {
+            println!("  \x1b[1;30mn/a\x1b[1;0m       {err}");
+            return Ok(());
+        }
     };
-
-    // Only print the table if the simple display is turned off
-    if !use_simple_display {
-        let mut table = Table::new(
-            switches
-                .into_iter()
-                .map(|f| TableDevice::from(ParsedDevice::from(f)))
-                .collect::<Vec<TableDevice>>(),
+    let Some(summary) = stats::summarize(&historic_data) else {
+        println!(
+            "  \x1b[1;30mn/a\x1b[1;0m       No power draw data was recorded in this timespan
+ => The server records the power draw periodically: please wait a few hours"
         );
-        table.with(Style::modern().remove_horizontal()).with(
-            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m"))),
-        );
-        println!("{}", table);
-    }
-
-    println!(
-        "=== Current Power Draw ===
-  Active  \x1b[1;32m*\x1b[1;0m {:>4} W ({:>3.0} %)
-  Passive \x1b[1;31m.\x1b[1;0m {:>4} W ({:>3.0} %)
-  Total   Σ {:>4} W (100 %)
-  ",
-        power_active,
-        power_active as f64 * 100.0 / power_total as f64,
-        power_passive,
-        power_passive as f64 * 100.0 / power_total as f64,
-        power_total,
-    );
+        return Ok(());
+    };
+    let costs = tariff::report(config, &historic_data, &timespan);
 
     println!(
-        "\n=== 24-Hour Metrics    ===
-  Used    Σ {:>3.2} KWh
+        "  Used    * {:>3.2} KWh
+  Off     . {:>3.2} KWh
+  Total   Σ {:>3.2} KWh
   Cost      {:>3.2} {}
+  Average   {:>3.0} W
+  Minimum   {:>3} W
   Peak      {:>3} W",
-        kwh_24_hours,
-        kwh_24_hours * config.cost_per_kwh,
-        config.unit_symbol,
-        peak_24_hours,
+        summary.energy.on_kwh,
+        summary.energy.off_kwh,
+        summary.energy.total_kwh(),
+        costs.total(),
+        config.currency,
+        summary.average,
+        summary.minimum,
+        summary.peak,
     );
+    for (percentile, watts) in &summary.percentiles {
+        println!("  P{percentile:<7} {watts:>3} W");
+    }
+    if !summary.energy.gaps.is_empty() {
+        let missing_ms: u64 = summary.energy.gaps.iter().map(Gap::duration_ms).sum();
+        println!(
+            "  \x1b[1;33mGaps\x1b[1;0m      {} ({:.1} h without data are not accounted for)",
+            summary.energy.gaps.len(),
+            missing_ms as f64 / 1000.0 / 60.0 / 60.0
+        );
+    }
+
+    // The breakdown is only useful if there is more than the default price
+    if !config.tariffs.is_empty() || costs.standing_charge > 0.0 {
+        println!("\n=== Cost Breakdown ===");
+        tariff::print_report(&costs, config);
+    }
+
+    if let Some(resolution) = resolution {
+        println!();
+        stats::print_aggregated(&historic_data, resolution, config);
+    }
+
+    if show_chart {
+        println!("\n=== Power Draw History ===");
+        chart::print(&historic_data);
+    }
 
     Ok(())
 }
 
-/// Analyzes the data and returns how much power has been used during the timespan of the input
-/// Only accounts for the on-power (which has been used)
-fn kwh_total(data: &[PowerDrawPoint]) -> Result<f64> {
-    let mut data = data.iter();
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-    let mut prev: &PowerDrawPoint = match data.next() {
-        Some(p) => p,
-        None => return Err(Error::NotEnoughPowerDrawData),
-    };
+    fn device(watts: Option<usize>, status: bool) -> ParsedDevice {
+        ParsedDevice {
+            id: "device".to_string(),
+            name: "Device".to_string(),
+            room_id: "room".to_string(),
+            power: watts.map(|watts| ParsedPower { status, watts }),
+        }
+    }
 
-    let mut sum = 0.0;
+    #[test]
+    fn current_draw_without_devices() {
+        let draw = CurrentDraw::from([].as_slice());
+        assert_eq!(draw.total(), 0);
+        assert_eq!(draw.share(draw.active), None);
+        assert_eq!(display_share(draw.share(draw.passive)), "n/a");
+    }
 
-    for point in data {
-        let duration_minutes = (point.time - prev.time) / 1000 / 60;
-        sum += point.on.watts as f64 * (duration_minutes as f64 / 60.0) / 1000.0;
-        prev = point;
+    #[test]
+    fn current_draw_without_power_information() {
+        let draw = CurrentDraw::from([device(None, true), device(Some(0), true)].as_slice());
+        assert_eq!(draw, CurrentDraw::default());
+        assert_eq!(draw.share(draw.active), None);
     }
 
-    Ok(sum)
+    #[test]
+    fn current_draw_all_off() {
+        let draw = CurrentDraw::from([device(Some(60), false), device(Some(40), false)].as_slice());
+        assert_eq!(
+            draw,
+            CurrentDraw {
+                active: 0,
+                passive: 100
+            }
+        );
+        assert_eq!(draw.share(draw.active), Some(0.0));
+        assert_eq!(draw.share(draw.passive), Some(100.0));
+    }
 }
@@ -1,16 +1,51 @@
+use crate::config;
+use rustyline::error::ReadlineError;
 use smarthome_sdk_rs::Error as SdkError;
-use std::fmt::Display;
+use std::{fmt::Display, io, time::Duration};
 
 pub type Result<T> = std::result::Result<T, Error>;
 
+#[allow(clippy::enum_variant_names)]
 pub enum Error {
     GetDevices(SdkError),
     GetPowerDrawData(SdkError),
     Unknown(SdkError),
     InvalidSwitch(String),
+    SwitchNotFound {
+        query: String,
+        suggestions: Vec<String>,
+    },
+    NoSelection,
+    Picker(ReadlineError),
+    NoPowerCapability(String),
+    InvalidGlob(String),
     PermissionDenied(String),
     NotEnoughPowerDrawData,
+    InvalidTimespan,
+    SwitchingFailed {
+        failed: usize,
+        total: usize,
+    },
     ServerError,
+    SceneDoesNotExist(String),
+    SceneAlreadyExists(String),
+    InvalidScene(String),
+    Config(config::Error),
+    Journal(io::Error),
+    NothingToUndo,
+    Terminal(io::Error),
+    Samples(io::Error),
+    NoDeviceSamples,
+    Export(io::Error),
+    VerificationFailed {
+        mismatched: usize,
+        total: usize,
+    },
+    WaitTimeout {
+        switch_id: String,
+        power_on: bool,
+        timeout: Duration,
+    },
 }
 
 impl Display for Error {
@@ -21,9 +56,42 @@ impl Display for Error {
             match self {
                 Self::InvalidSwitch(switch_id) =>
                     format!("The switch `{switch_id}` does not exist"),
+                Self::SwitchNotFound { query, suggestions } => match suggestions.is_empty() {
+                    true => format!("The switch `{query}` does not exist"),
+                    false => format!(
+                        "The switch `{query}` does not exist\n => Did you mean {}?",
+                        suggestions
+                            .iter()
+                            .map(|id| format!("`{id}`"))
+                            .collect::<Vec<String>>()
+                            .join(", ")
+                    ),
+                },
+                Self::NoSelection => "No switches were selected\n => Specify switch-ids or names, or use `--all`, `--room` or `--name-glob`".to_string(),
+                Self::Picker(err) => format!("Could not pick switches: {err}"),
+                Self::NoPowerCapability(switch_id) =>
+                    format!("The device `{switch_id}` does not support power"),
+                Self::InvalidGlob(message) => format!("Invalid glob pattern: {message}"),
                 Self::PermissionDenied(switch_id) => format!("You are either lacking permission to use switches or you do not have access to the switch `{switch_id}`"),
                 Self::GetDevices(err) => format!("Could not get devices: {err}"),
                     Self::NotEnoughPowerDrawData => "Not enough power draw data: averaging requires more power draw data: please wait a few hours".to_string(),
+                Self::InvalidTimespan => "Invalid timespan: the start must be before the end and both must be within the supported time range".to_string(),
+                Self::SwitchingFailed { failed, total } => format!("{failed} of {total} switch(es) could not be switched"),
+                Self::SceneDoesNotExist(name) => format!("The scene `{name}` does not exist"),
+                Self::SceneAlreadyExists(name) => format!("The scene `{name}` already exists\n => Use `--force` to overwrite it"),
+                Self::InvalidScene(message) => format!("Invalid scene: {message}"),
+                Self::Config(err) => format!("Could not update config file: {err}"),
+                Self::Journal(err) => format!("Could not access power journal: {err}"),
+                Self::NothingToUndo => "There are no power changes which could be undone".to_string(),
+                Self::Samples(err) => format!("Could not access device samples: {err}"),
+                Self::NoDeviceSamples => "No device states were recorded during this timespan\n => Keep `power top` or `serve-metrics` running to record device states".to_string(),
+                Self::Export(err) => format!("Could not export power draw data: {err}"),
+                Self::VerificationFailed { mismatched, total } => format!("{mismatched} of {total} switch(es) did not reach their target state"),
+                Self::WaitTimeout { switch_id, power_on, timeout } => format!(
+                    "The switch `{switch_id}` did not turn {} within {timeout:?}",
+                    if *power_on { "on" } else { "off" }
+                ),
+                Self::Terminal(err) => format!("Terminal error: {err}"),
                 Self::ServerError => "The server was unable to handle this switch".to_string(),
                 Self::Unknown(err) => format!("Unknown error: {err}"),
                 Self::GetPowerDrawData(err) => format!("Could not get power draw data: {err}"),
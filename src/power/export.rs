@@ -0,0 +1,231 @@
+use std::{
+    fmt::Write as _,
+    fs,
+    io::{self, Write},
+};
+
+use chrono::{Local, TimeZone};
+use log::info;
+use serde::Serialize;
+use smarthome_sdk_rs::{Client, PowerDrawPoint};
+
+use super::{
+    errors::{Error, Result},
+    stats::{self, Timespan},
+};
+use crate::cli::ExportFormat;
+
+/// A power draw point in a flat representation which is suitable for exporting
+#[derive(Serialize)]
+struct ExportPoint {
+    /// Unix timestamp in milliseconds
+    time: u64,
+    /// The same point in time as an RFC 3339 string in local time
+    timestamp: String,
+    on_switches: usize,
+    on_watts: usize,
+    on_percent: f64,
+    off_switches: usize,
+    off_watts: usize,
+    off_percent: f64,
+}
+
+impl From<&PowerDrawPoint> for ExportPoint {
+    fn from(source: &PowerDrawPoint) -> Self {
+        Self {
+            time: source.time,
+            timestamp: match Local.timestamp_millis_opt(source.time as i64).single() {
+                Some(time) => time.to_rfc3339(),
+                None => String::new(),
+            },
+            on_switches: source.on.switch_count,
+            on_watts: source.on.watts,
+            on_percent: source.on.percent,
+            off_switches: source.off.switch_count,
+            off_watts: source.off.watts,
+            off_percent: source.off.percent,
+        }
+    }
+}
+
+/// The metric families of the OpenMetrics export: name, type, help and the extractor of the value
+type MetricFamily = (&'static str, &'static str, fn(&ExportPoint) -> f64);
+
+const METRIC_FAMILIES: [MetricFamily; 6] = [
+    (
+        "smarthome_power_on_watts",
+        "Power drawn by switches which are turned on",
+        |point| point.on_watts as f64,
+    ),
+    (
+        "smarthome_power_off_watts",
+        "Power which switches that are turned off would draw",
+        |point| point.off_watts as f64,
+    ),
+    (
+        "smarthome_power_on_switches",
+        "Number of switches which are turned on",
+        |point| point.on_switches as f64,
+    ),
+    (
+        "smarthome_power_off_switches",
+        "Number of switches which are turned off",
+        |point| point.off_switches as f64,
+    ),
+    (
+        "smarthome_power_on_percent",
+        "Share of the total power drawn by switches which are turned on",
+        |point| point.on_percent,
+    ),
+    (
+        "smarthome_power_off_percent",
+        "Share of the total power which switches that are turned off would draw",
+        |point| point.off_percent,
+    ),
+];
+
+/// Exports the power draw data of the timespan to the output file or stdout
+pub async fn export(
+    client: &Client,
+    format: ExportFormat,
+    output: Option<&str>,
+    timespan: Timespan,
+) -> Result<()> {
+    if timespan.since >= timespan.until {
+        return Err(Error::InvalidTimespan);
+    }
+    let points: Vec<ExportPoint> = stats::history(client, &timespan)
+        .await?
+        .iter()
+        .map(ExportPoint::from)
+        .collect();
+
+    let content = match format {
+        ExportFormat::Csv => csv(&points),
+        ExportFormat::Json => {
+            let mut json =
+                serde_json::to_string_pretty(&points).expect("Points can always be serialized");
+            json.push('\n');
+            json
+        }
+        ExportFormat::OpenMetrics => openmetrics(&points),
+    };
+
+    match output {
+        Some(path) => {
+            fs::write(path, content).map_err(Error::Export)?;
+            info!("Exported {} power draw point(s) to `{path}`", points.len());
+        }
+        None => io::stdout()
+            .write_all(content.as_bytes())
+            .map_err(Error::Export)?,
+    }
+    Ok(())
+}
+
+fn csv(points: &[ExportPoint]) -> String {
+    let mut csv = String::from(
+        "time,timestamp,on_switches,on_watts,on_percent,off_switches,off_watts,off_percent\n",
+    );
+    for point in points {
+        writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{}",
+            point.time,
+            point.timestamp,
+            point.on_switches,
+            point.on_watts,
+            point.on_percent,
+            point.off_switches,
+            point.off_watts,
+            point.off_percent
+        )
+        .expect("Writing to a string cannot fail");
+    }
+    csv
+}
+
+/// Formats the points as OpenMetrics gauges, each sample carries the timestamp of its point (in seconds)
+fn openmetrics(points: &[ExportPoint]) -> String {
+    let mut metrics = String::new();
+    for (name, help, value) in METRIC_FAMILIES {
+        writeln!(metrics, "# TYPE {name} gauge\n# HELP {name} {help}")
+            .expect("Writing to a string cannot fail");
+        for point in points {
+            writeln!(
+                metrics,
+                "{name} {} {}.{:03}",
+                value(point),
+                point.time / 1000,
+                point.time % 1000
+            )
+            .expect("Writing to a string cannot fail");
+        }
+    }
+    metrics.push_str("# EOF\n");
+    metrics
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::fixtures::point;
+
+    fn points() -> Vec<ExportPoint> {
+        [
+            point(1_700_000_000_123, 120, 30),
+            point(1_700_000_060_000, 0, 150),
+        ]
+        .iter()
+        .map(ExportPoint::from)
+        .collect()
+    }
+
+    #[test]
+    fn csv_has_header_and_one_row_per_point() {
+        let csv = csv(&points());
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(
+            lines[0],
+            "time,timestamp,on_switches,on_watts,on_percent,off_switches,off_watts,off_percent"
+        );
+        assert_eq!(lines.len(), 3);
+
+        let row: Vec<&str> = lines[1].split(',').collect();
+        assert_eq!(row.len(), 8);
+        assert_eq!(row[0], "1700000000123");
+        assert!(chrono::DateTime::parse_from_rfc3339(row[1]).is_ok());
+        assert_eq!(row[2..], ["1", "120", "0", "1", "30", "0"]);
+        assert!(lines[2].starts_with("1700000060000,"));
+    }
+
+    #[test]
+    fn openmetrics_declares_every_family_and_ends_with_eof() {
+        let metrics = openmetrics(&points());
+        for (name, help, _) in METRIC_FAMILIES {
+            assert!(metrics.contains(&format!("# TYPE {name} gauge\n# HELP {name} {help}\n")));
+        }
+        assert!(metrics.ends_with("# EOF\n"));
+        assert_eq!(metrics.matches("# EOF").count(), 1);
+    }
+
+    #[test]
+    fn openmetrics_timestamps_are_in_seconds() {
+        let metrics = openmetrics(&points());
+        assert!(metrics.contains("\nsmarthome_power_on_watts 120 1700000000.123\n"));
+        assert!(metrics.contains("\nsmarthome_power_off_watts 150 1700000060.000\n"));
+    }
+
+    #[test]
+    fn empty_dataset() {
+        assert_eq!(
+            csv(&[]),
+            "time,timestamp,on_switches,on_watts,on_percent,off_switches,off_watts,off_percent\n"
+        );
+        let metrics = openmetrics(&[]);
+        assert_eq!(metrics.lines().count(), METRIC_FAMILIES.len() * 2 + 1);
+        assert!(metrics.lines().all(|line| line.starts_with("# TYPE ")
+            || line.starts_with("# HELP ")
+            || line == "# EOF"));
+    }
+}
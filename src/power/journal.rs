@@ -0,0 +1,238 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{Local, TimeZone};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use smarthome_sdk_rs::Client;
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    errors::{Error, Result},
+    switch::{self, PowerJob},
+};
+use crate::config;
+
+/// How many entries the journal holds before it is rotated, older entries are discarded on the next rotation
+const MAX_ENTRIES: usize = 100;
+
+/// All power changes which were performed by a single command
+#[derive(Serialize, Deserialize)]
+pub struct Batch {
+    /// Unix timestamp in milliseconds
+    pub time: i64,
+    /// The URL of the server on which the changes were performed
+    pub server: String,
+    /// A short description of the command which caused the changes
+    pub label: String,
+    pub changes: Vec<Change>,
+}
+
+/// A single line of the journal
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Entry {
+    Batch(Batch),
+    /// Marks the batch with the given time and server as undone
+    Undo {
+        time: i64,
+        server: String,
+    },
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Change {
+    pub switch_id: String,
+    /// The state before the change, `None` if it was unknown
+    pub previous: Option<bool>,
+    pub new: bool,
+}
+
+#[derive(Tabled)]
+struct TableBatch {
+    #[tabled(rename = "Time")]
+    time: String,
+    #[tabled(rename = "Command")]
+    label: String,
+    #[tabled(rename = "Changes")]
+    changes: String,
+}
+
+impl From<&Batch> for TableBatch {
+    fn from(source: &Batch) -> Self {
+        Self {
+            time: match Local.timestamp_millis_opt(source.time) {
+                chrono::LocalResult::Single(time) => time.format("%Y-%m-%d %H:%M:%S").to_string(),
+                _ => "?".to_string(),
+            },
+            label: source.label.clone(),
+            changes: source
+                .changes
+                .iter()
+                .map(|change| {
+                    format!(
+                        "{}: {} -> {}",
+                        change.switch_id,
+                        display_state(change.previous),
+                        display_state(Some(change.new))
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join("\n"),
+        }
+    }
+}
+
+fn display_state(state: Option<bool>) -> &'static str {
+    match state {
+        Some(true) => "\x1b[1;32mON\x1b[1;0m",
+        Some(false) => "\x1b[1;31mOFF\x1b[1;0m",
+        None => "\x1b[1;30m?\x1b[1;0m",
+    }
+}
+
+fn file_path() -> Result<PathBuf> {
+    match config::state_dir() {
+        Some(dir) => Ok(PathBuf::from(dir).join("power-journal.jsonl")),
+        None => Err(Error::Journal(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Could not determine state directory: do you have a home?",
+        ))),
+    }
+}
+
+/// The journal is rotated into this file, which is still read
+fn rotated_path(path: &Path) -> PathBuf {
+    path.with_extension("jsonl.old")
+}
+
+/// Returns all batches which have not been undone, oldest first
+fn read() -> Result<Vec<Batch>> {
+    let path = file_path()?;
+    let mut batches = vec![];
+    let mut undone = vec![];
+    for path in [rotated_path(&path), path] {
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(Error::Journal(err)),
+        };
+        for line in content.lines() {
+            match serde_json::from_str::<Entry>(line) {
+                Ok(Entry::Batch(batch)) => batches.push(batch),
+                Ok(Entry::Undo { time, server }) => undone.push((time, server)),
+                // An interrupted write may leave a broken line behind, which is simply skipped
+                Err(err) => debug!("Skipping invalid journal entry: {err}"),
+            }
+        }
+    }
+    batches.retain(|batch| !undone.contains(&(batch.time, batch.server.clone())));
+    Ok(batches)
+}
+
+/// Appends a single entry, so that entries of commands which run at the same time cannot get lost
+/// Once the journal holds too many entries, it replaces the previously rotated journal
+fn append(entry: &Entry) -> Result<()> {
+    let path = file_path()?;
+    fs::create_dir_all(path.parent().expect("Journal path always has a parent"))
+        .map_err(Error::Journal)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .map_err(Error::Journal)?;
+    file.write_all(
+        format!(
+            "{}\n",
+            serde_json::to_string(entry).expect("Journal entries can always be serialized")
+        )
+        .as_bytes(),
+    )
+    .map_err(Error::Journal)?;
+
+    let entries = fs::read_to_string(&path)
+        .map_err(Error::Journal)?
+        .lines()
+        .count();
+    if entries > MAX_ENTRIES {
+        debug!("Rotating the power journal...");
+        match fs::rename(&path, rotated_path(&path)) {
+            // Another command may have rotated the journal in the meantime
+            Err(err) if err.kind() != io::ErrorKind::NotFound => return Err(Error::Journal(err)),
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Appends a batch of changes to the journal
+pub fn record(batch: Batch) -> Result<()> {
+    debug!(
+        "Recording {} power change(s) in the journal...",
+        batch.changes.len()
+    );
+    append(&Entry::Batch(batch))
+}
+
+/// Reverts the most recent batch of changes which was performed on the current server
+pub async fn undo(client: &Client, concurrency: usize) -> Result<()> {
+    let mut batches = read()?;
+    let server = client.smarthome_url.to_string();
+    let Some(index) = batches.iter().rposition(|batch| batch.server == server) else {
+        return Err(Error::NothingToUndo);
+    };
+
+    let jobs: Vec<PowerJob> = batches[index]
+        .changes
+        .iter()
+        .filter_map(|change| {
+            change.previous.map(|previous| PowerJob {
+                switch_id: change.switch_id.clone(),
+                previous: Some(change.new),
+                power_on: previous,
+            })
+        })
+        .collect();
+
+    if !jobs.is_empty() {
+        println!("Reverting `{}`...", batches[index].label);
+        switch::execute_jobs(client, jobs, concurrency, None)
+            .await
+            .result?;
+    }
+
+    // The batch is only marked as undone once every change has been reverted successfully
+    let batch = batches.swap_remove(index);
+    append(&Entry::Undo {
+        time: batch.time,
+        server: batch.server,
+    })
+}
+
+pub fn history(client: &Client, limit: usize) -> Result<()> {
+    let batches = read()?;
+    let server = client.smarthome_url.to_string();
+    let mut recent: Vec<TableBatch> = batches
+        .iter()
+        .rev()
+        .filter(|batch| batch.server == server)
+        .take(limit)
+        .map(TableBatch::from)
+        .collect();
+    recent.reverse();
+
+    let mut table = Table::new(recent);
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+    Ok(())
+}
@@ -1,23 +1,234 @@
+use chrono::Local;
+use log::info;
 use smarthome_sdk_rs::Client;
 
-use crate::{cli::PowerCommand, config::Config};
+use crate::{
+    cli::{PowerCommand, PowerState, SceneCommand, SwitchSelector, TimerArgs, TimespanArgs},
+    config::Config,
+};
 
+pub use draw::ParsedDevice;
 use errors::Error;
+pub use samples::Recorder;
+use stats::Timespan;
 
+mod attribution;
+mod chart;
 mod draw;
 mod errors;
+mod export;
+mod journal;
+mod picker;
+mod samples;
+mod scene;
+mod select;
+mod state;
+mod stats;
 mod switch;
+mod tariff;
+mod timer;
+mod top;
 
 pub async fn handle_subcommand(
     command: PowerCommand,
     client: &Client,
     config: &Config,
+    config_path: &str,
 ) -> Result<(), Error> {
     match command {
-        PowerCommand::Devices { all } => switch::switch_list(client, all).await,
-        PowerCommand::Draw { simple } => draw::power_draw(client, &config.power, simple).await,
-        PowerCommand::Toggle { switch_ids } => switch::toggle_power(client, &switch_ids).await,
-        PowerCommand::On { switch_ids } => switch::set_power(client, &switch_ids, true).await,
-        PowerCommand::Off { switch_ids } => switch::set_power(client, &switch_ids, false).await,
+        PowerCommand::Devices { all, by_room, room } => {
+            switch::switch_list(client, all, by_room, &room).await
+        }
+        PowerCommand::Draw {
+            simple,
+            timespan,
+            per,
+            chart,
+        } => {
+            draw::power_draw(
+                client,
+                &config.power,
+                simple,
+                timespan_of(&timespan)?,
+                per,
+                chart,
+            )
+            .await
+        }
+        PowerCommand::Export {
+            format,
+            output,
+            timespan,
+        } => {
+            export::export(
+                client,
+                format,
+                output.as_deref(),
+                timespan_of(&timespan)?.unwrap_or_else(Timespan::last_day),
+            )
+            .await
+        }
+        PowerCommand::Energy { timespan } => attribution::show(
+            client,
+            &config.power,
+            timespan_of(&timespan)?.unwrap_or_else(Timespan::last_day),
+        ),
+        PowerCommand::Toggle {
+            selector,
+            group,
+            jobs,
+            verify,
+        } => {
+            let devices = select::resolve(client, &selector).await?;
+            if devices.is_empty() {
+                info!("No switches matched the selection");
+                return Ok(());
+            }
+            switch::toggle_power(
+                client,
+                &devices,
+                group,
+                jobs.unwrap_or(config.power.concurrency),
+                verify,
+            )
+            .await
+        }
+        PowerCommand::On {
+            selector,
+            timer,
+            jobs,
+            verify,
+        } => {
+            set_power_selection(
+                client,
+                &selector,
+                &timer,
+                true,
+                jobs.unwrap_or(config.power.concurrency),
+                verify,
+            )
+            .await
+        }
+        PowerCommand::Off {
+            selector,
+            timer,
+            jobs,
+            verify,
+        } => {
+            set_power_selection(
+                client,
+                &selector,
+                &timer,
+                false,
+                jobs.unwrap_or(config.power.concurrency),
+                verify,
+            )
+            .await
+        }
+        PowerCommand::Wait {
+            switch_id,
+            state,
+            timeout,
+            interval,
+        } => {
+            state::wait(
+                client,
+                &switch_id,
+                state == PowerState::On,
+                timeout.0,
+                interval.0,
+            )
+            .await
+        }
+        PowerCommand::Top { interval } => top::start(client, &config.power, interval.0).await,
+        PowerCommand::Undo { jobs } => {
+            journal::undo(client, jobs.unwrap_or(config.power.concurrency)).await
+        }
+        PowerCommand::History { limit } => journal::history(client, limit),
+        PowerCommand::Scene(sub) => match sub {
+            SceneCommand::Ls => scene::list(config),
+            SceneCommand::Apply { name, jobs } => {
+                scene::apply(
+                    client,
+                    config,
+                    &name,
+                    jobs.unwrap_or(config.power.concurrency),
+                )
+                .await
+            }
+            SceneCommand::Save {
+                name,
+                switch_ids,
+                force,
+            } => scene::save(client, config, config_path, &name, &switch_ids, force).await,
+            SceneCommand::Diff { name } => scene::diff(client, config, &name).await,
+            SceneCommand::Del { name } => scene::delete(config, config_path, &name),
+        },
+    }
+}
+
+async fn set_power_selection(
+    client: &Client,
+    selector: &SwitchSelector,
+    timer: &TimerArgs,
+    power_on: bool,
+    concurrency: usize,
+    verify: bool,
+) -> Result<(), Error> {
+    let devices = select::resolve(client, selector).await?;
+    if devices.is_empty() {
+        info!("No switches matched the selection");
+        return Ok(());
+    }
+    match (&timer.delay, &timer.duration) {
+        (None, None) => {
+            switch::set_power(client, &devices, power_on, concurrency, verify)
+                .await
+                .result
+        }
+        _ => timer::run(client, &devices, power_on, concurrency, verify, timer).await,
+    }
+}
+
+/// Returns the selected timespan, `None` if no argument was given
+fn timespan_of(args: &TimespanArgs) -> Result<Option<Timespan>, Error> {
+    if args.range.is_none() && args.since.is_none() && args.until.is_none() {
+        return Ok(None);
+    }
+    let until = args.until.map_or_else(Local::now, |until| until.0);
+    let since = match (args.since, args.range) {
+        (Some(since), _) => Some(since.0),
+        (None, Some(range)) => chrono::Duration::from_std(range.0)
+            .ok()
+            .and_then(|range| until.checked_sub_signed(range)),
+        (None, None) => until.checked_sub_signed(chrono::Duration::hours(24)),
+    };
+    match since {
+        Some(since) => Ok(Some(Timespan { since, until })),
+        None => Err(Error::InvalidTimespan),
+    }
+}
+
+/// Fixtures which are shared by the tests of the power modules
+#[cfg(test)]
+mod fixtures {
+    use smarthome_sdk_rs::{PowerDrawData, PowerDrawPoint};
+
+    /// A power draw point with one switch turned on and one turned off
+    pub fn point(time: u64, on_watts: usize, off_watts: usize) -> PowerDrawPoint {
+        PowerDrawPoint {
+            id: time,
+            time,
+            on: PowerDrawData {
+                switch_count: 1,
+                watts: on_watts,
+                percent: 0.0,
+            },
+            off: PowerDrawData {
+                switch_count: 1,
+                watts: off_watts,
+                percent: 0.0,
+            },
+        }
     }
 }
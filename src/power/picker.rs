@@ -0,0 +1,96 @@
+use rustyline::{error::ReadlineError, DefaultEditor};
+
+use super::{
+    draw::ParsedDevice,
+    errors::{Error, Result},
+    select,
+};
+
+/// How many candidates are displayed at once
+const MAX_CANDIDATES: usize = 15;
+
+/// Lets the user interactively pick switches from the given devices
+/// Typing text filters the candidates, typing their numbers selects them
+/// Returns the IDs of the picked switches or an empty list if the user aborted
+pub fn pick(devices: &[ParsedDevice]) -> Result<Vec<String>> {
+    let mut editor = DefaultEditor::new().map_err(Error::Picker)?;
+    let mut query = String::new();
+
+    loop {
+        let candidates = rank(devices, &query);
+        if candidates.is_empty() {
+            println!("No switches match `{query}`");
+        }
+        for (index, device) in candidates.iter().enumerate() {
+            println!(
+                "\x1b[1;90m{:>3}\x1b[1;0m  {} \x1b[90m({})\x1b[0m{}",
+                index + 1,
+                device.name,
+                device.id,
+                match &device.power {
+                    Some(power) if power.status => " \x1b[1;32mON\x1b[1;0m",
+                    Some(_) => " \x1b[1;31mOFF\x1b[1;0m",
+                    None => "",
+                }
+            );
+        }
+        println!("\x1b[90mType to filter, enter numbers (for example `1 3`) to select, leave empty to abort\x1b[0m");
+
+        let line = match editor.readline("\x1b[1;32mswitch>\x1b[1;0m ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted | ReadlineError::Eof) => return Ok(vec![]),
+            Err(err) => return Err(Error::Picker(err)),
+        };
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let numbers: Option<Vec<usize>> = line
+            .split(|c: char| c == ',' || c.is_whitespace())
+            .filter(|part| !part.is_empty())
+            .map(|part| part.parse().ok())
+            .collect();
+        match numbers {
+            Some(numbers) => {
+                match numbers
+                    .iter()
+                    .map(|number| candidates.get(number.wrapping_sub(1)))
+                    .collect::<Option<Vec<&&ParsedDevice>>>()
+                {
+                    Some(picked) => return Ok(picked.iter().map(|device| device.id.clone()).collect()),
+                    None => println!("\x1b[1;31mInvalid selection:\x1b[1;0m please choose numbers between 1 and {}", candidates.len()),
+                }
+            }
+            None => query = line.to_string(),
+        }
+    }
+}
+
+/// Returns the devices which fuzzily match the query, best matches first
+fn rank<'devices>(devices: &'devices [ParsedDevice], query: &str) -> Vec<&'devices ParsedDevice> {
+    let query = query.to_lowercase();
+    let mut matches: Vec<(usize, &ParsedDevice)> = devices
+        .iter()
+        .filter(|device| {
+            is_subsequence(&query, &device.id.to_lowercase())
+                || is_subsequence(&query, &device.name.to_lowercase())
+        })
+        .map(|device| match query.is_empty() {
+            true => (0, device),
+            false => (select::distance(&query, device), device),
+        })
+        .collect();
+    matches.sort_by_key(|(distance, _)| *distance);
+    matches
+        .into_iter()
+        .take(MAX_CANDIDATES)
+        .map(|(_, device)| device)
+        .collect()
+}
+
+/// Whether all characters of the needle appear in the haystack in the same order
+fn is_subsequence(needle: &str, haystack: &str) -> bool {
+    let mut haystack = haystack.chars();
+    needle.chars().all(|c| haystack.any(|h| h == c))
+}
@@ -0,0 +1,199 @@
+use std::{
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use log::debug;
+use serde::{Deserialize, Serialize};
+use smarthome_sdk_rs::Client;
+
+use super::{
+    draw::ParsedDevice,
+    errors::{Error, Result},
+};
+use crate::config;
+
+/// Samples which are older than this are discarded
+const MAX_SAMPLE_AGE_DAYS: i64 = 31;
+/// Samples are stored in one file per day (in UTC), named `device-samples-YYYY-MM-DD.jsonl`
+const FILE_PREFIX: &str = "device-samples-";
+const FILE_SUFFIX: &str = ".jsonl";
+/// Unchanged device states are only recorded again after this many milliseconds
+const MIN_SAMPLE_INTERVAL_MS: i64 = 60 * 1000;
+
+/// The power state of all devices at a point in time
+#[derive(Serialize, Deserialize)]
+pub struct Sample {
+    /// Unix timestamp in milliseconds
+    pub time: i64,
+    /// The URL of the server from which the states were fetched
+    pub server: String,
+    pub devices: Vec<DeviceSample>,
+}
+
+#[derive(Serialize, Deserialize, PartialEq, Eq)]
+pub struct DeviceSample {
+    pub id: String,
+    pub name: String,
+    pub power_on: bool,
+    pub watts: usize,
+}
+
+/// Records samples while polling, samples in which no device changed are throttled
+#[derive(Default)]
+pub struct Recorder {
+    last: Option<Sample>,
+}
+
+impl Recorder {
+    pub fn record(&mut self, client: &Client, devices: &[ParsedDevice]) -> Result<()> {
+        let sample = Sample::new(client, devices);
+        if let Some(last) = &self.last {
+            if last.devices == sample.devices && sample.time - last.time < MIN_SAMPLE_INTERVAL_MS {
+                return Ok(());
+            }
+        }
+        append(&sample)?;
+        self.last = Some(sample);
+        Ok(())
+    }
+}
+
+impl Sample {
+    fn new(client: &Client, devices: &[ParsedDevice]) -> Self {
+        Self {
+            time: Utc::now().timestamp_millis(),
+            server: client.smarthome_url.to_string(),
+            devices: devices
+                .iter()
+                .filter_map(|device| {
+                    device.power.as_ref().map(|power| DeviceSample {
+                        id: device.id.clone(),
+                        name: device.name.clone(),
+                        power_on: power.status,
+                        watts: power.watts,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+fn dir_path() -> Result<PathBuf> {
+    match config::state_dir() {
+        Some(dir) => Ok(PathBuf::from(dir)),
+        None => Err(Error::Samples(io::Error::new(
+            io::ErrorKind::NotFound,
+            "Could not determine state directory: do you have a home?",
+        ))),
+    }
+}
+
+fn file_path(dir: &Path, day: NaiveDate) -> PathBuf {
+    dir.join(format!(
+        "{FILE_PREFIX}{}{FILE_SUFFIX}",
+        day.format("%Y-%m-%d")
+    ))
+}
+
+/// Returns all sample files in the directory together with the day whose samples they contain
+fn files(dir: &Path) -> Result<Vec<(NaiveDate, PathBuf)>> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(err) => return Err(Error::Samples(err)),
+    };
+    let mut files = vec![];
+    for entry in entries {
+        let path = entry.map_err(Error::Samples)?.path();
+        let day = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(|name| name.strip_prefix(FILE_PREFIX)?.strip_suffix(FILE_SUFFIX))
+            .and_then(|day| NaiveDate::parse_from_str(day, "%Y-%m-%d").ok());
+        if let Some(day) = day {
+            files.push((day, path));
+        }
+    }
+    Ok(files)
+}
+
+/// Samples which were recorded before this time have expired
+fn oldest() -> DateTime<Utc> {
+    Utc::now() - Duration::days(MAX_SAMPLE_AGE_DAYS)
+}
+
+/// Records the current power state of the devices once
+pub fn record(client: &Client, devices: &[ParsedDevice]) -> Result<()> {
+    append(&Sample::new(client, devices))
+}
+
+/// Appends the sample to the file of the current day and removes the files which only contain expired samples
+/// Whole files are removed so that samples which are appended concurrently cannot get lost
+fn append(sample: &Sample) -> Result<()> {
+    debug!(
+        "Recording power state of {} device(s)...",
+        sample.devices.len()
+    );
+    let dir = dir_path()?;
+    fs::create_dir_all(&dir).map_err(Error::Samples)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file_path(&dir, Utc::now().date_naive()))
+        .map_err(Error::Samples)?;
+    writeln!(
+        file,
+        "{}",
+        serde_json::to_string(sample).expect("Samples can always be serialized")
+    )
+    .map_err(Error::Samples)?;
+
+    let oldest_day = oldest().date_naive();
+    for (day, path) in files(&dir)? {
+        if day < oldest_day {
+            debug!("Removing expired device samples of {day}...");
+            match fs::remove_file(path) {
+                // Another process may have removed the file in the meantime
+                Err(err) if err.kind() != io::ErrorKind::NotFound => {
+                    return Err(Error::Samples(err))
+                }
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns all samples of the current server which have not expired yet, sorted by time
+pub fn read(client: &Client) -> Result<Vec<Sample>> {
+    let oldest = oldest();
+    let server = client.smarthome_url.to_string();
+    let mut samples = vec![];
+    for (day, path) in files(&dir_path()?)? {
+        if day < oldest.date_naive() {
+            continue;
+        }
+        let content = match fs::read_to_string(&path) {
+            Ok(content) => content,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+            Err(err) => return Err(Error::Samples(err)),
+        };
+        for line in content.lines() {
+            match serde_json::from_str::<Sample>(line) {
+                Ok(sample)
+                    if sample.time >= oldest.timestamp_millis() && sample.server == server =>
+                {
+                    samples.push(sample)
+                }
+                Ok(_) => {}
+                // An interrupted write may leave a broken line behind, which is simply skipped
+                Err(err) => debug!("Skipping invalid device sample: {err}"),
+            }
+        }
+    }
+    samples.sort_by_key(|sample| sample.time);
+    Ok(samples)
+}
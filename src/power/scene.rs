@@ -0,0 +1,184 @@
+use std::collections::BTreeMap;
+
+use log::info;
+use smarthome_sdk_rs::Client;
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    draw::ParsedDevice,
+    errors::{Error, Result},
+    switch::{self, PowerJob},
+};
+use crate::config::{self, Config};
+
+#[derive(Tabled)]
+struct TableScene {
+    #[tabled(rename = "Scene")]
+    name: String,
+    #[tabled(rename = "Switches")]
+    switch_count: usize,
+    #[tabled(rename = "On")]
+    on_count: usize,
+    #[tabled(rename = "Off")]
+    off_count: usize,
+}
+
+#[derive(Tabled)]
+struct TableSceneDiff {
+    #[tabled(rename = "Switch")]
+    switch_id: String,
+    #[tabled(display_with("Self::display_state"), rename = "Current")]
+    current: Option<bool>,
+    #[tabled(display_with("Self::display_state"), rename = "Scene")]
+    desired: Option<bool>,
+    #[tabled(rename = "Status")]
+    status: &'static str,
+}
+
+impl TableSceneDiff {
+    fn display_state(state: &Option<bool>) -> String {
+        match state {
+            Some(true) => "\x1b[1;32mON\x1b[1;0m".to_string(),
+            Some(false) => "\x1b[1;31mOFF\x1b[1;0m".to_string(),
+            None => "\x1b[1;30mN/A\x1b[1;0m".to_string(),
+        }
+    }
+}
+
+pub fn list(config: &Config) -> Result<()> {
+    let mut table = Table::new(config.scenes.iter().map(|(name, states)| TableScene {
+        name: name.clone(),
+        switch_count: states.len(),
+        on_count: states.values().filter(|state| **state).count(),
+        off_count: states.values().filter(|state| !**state).count(),
+    }));
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+    Ok(())
+}
+
+pub async fn apply(client: &Client, config: &Config, name: &str, concurrency: usize) -> Result<()> {
+    let scene = get_scene(config, name)?;
+    let current = current_states(client).await?;
+
+    let jobs: Vec<PowerJob> = scene
+        .iter()
+        .filter(|(switch_id, desired)| current.get(*switch_id) != Some(desired))
+        .map(|(switch_id, desired)| PowerJob {
+            switch_id: switch_id.clone(),
+            previous: current.get(switch_id).copied(),
+            power_on: *desired,
+        })
+        .collect();
+
+    if jobs.is_empty() {
+        info!("Scene `{name}` is already active");
+        return Ok(());
+    }
+    switch::execute_jobs(
+        client,
+        jobs,
+        concurrency,
+        Some(&format!("power scene apply {name}")),
+    )
+    .await
+    .result
+}
+
+pub async fn diff(client: &Client, config: &Config, name: &str) -> Result<()> {
+    let scene = get_scene(config, name)?;
+    let current = current_states(client).await?;
+
+    let mut table = Table::new(scene.iter().map(|(switch_id, desired)| {
+        let current = current.get(switch_id).copied();
+        TableSceneDiff {
+            switch_id: switch_id.clone(),
+            current,
+            desired: Some(*desired),
+            status: match current {
+                Some(current) if current == *desired => "unchanged",
+                Some(_) => "differs",
+                None => "unavailable",
+            },
+        }
+    }));
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+    Ok(())
+}
+
+pub async fn save(
+    client: &Client,
+    config: &Config,
+    config_path: &str,
+    name: &str,
+    switch_ids: &[String],
+    overwrite: bool,
+) -> Result<()> {
+    if config.scenes.contains_key(name) && !overwrite {
+        return Err(Error::SceneAlreadyExists(name.to_string()));
+    }
+
+    let current = current_states(client).await?;
+    if let Some(unknown) = switch_ids.iter().find(|id| !current.contains_key(*id)) {
+        return Err(Error::InvalidSwitch(unknown.to_string()));
+    }
+
+    let scene: BTreeMap<String, bool> = current
+        .into_iter()
+        .filter(|(switch_id, _)| switch_ids.is_empty() || switch_ids.contains(switch_id))
+        .collect();
+    if scene.is_empty() {
+        return Err(Error::InvalidScene(
+            "a scene must contain at least one switch".to_string(),
+        ));
+    }
+
+    config::write_scene(config_path, name, Some(&scene)).map_err(Error::Config)?;
+    let switch_count = scene.len();
+    info!("Successfully saved scene `{name}` containing {switch_count} switch(es)");
+    Ok(())
+}
+
+pub fn delete(config: &Config, config_path: &str, name: &str) -> Result<()> {
+    if !config.scenes.contains_key(name) {
+        return Err(Error::SceneDoesNotExist(name.to_string()));
+    }
+    config::write_scene(config_path, name, None).map_err(Error::Config)?;
+    info!("Successfully deleted scene `{name}`");
+    Ok(())
+}
+
+fn get_scene<'config>(
+    config: &'config Config,
+    name: &str,
+) -> Result<&'config BTreeMap<String, bool>> {
+    match config.scenes.get(name) {
+        Some(scene) => Ok(scene),
+        None => Err(Error::SceneDoesNotExist(name.to_string())),
+    }
+}
+
+/// Returns the current power state of every personal switch which supports power
+async fn current_states(client: &Client) -> Result<BTreeMap<String, bool>> {
+    let switches = match client.personal_switches().await {
+        Ok(response) => response,
+        Err(err) => return Err(Error::GetDevices(err)),
+    };
+    Ok(switches
+        .into_iter()
+        .map(ParsedDevice::from)
+        .filter_map(|device| device.power.map(|power| (device.id, power.status)))
+        .collect())
+}
@@ -0,0 +1,157 @@
+use std::io::{self, IsTerminal};
+
+use glob::Pattern;
+use log::debug;
+use smarthome_sdk_rs::Client;
+
+use super::{
+    draw::ParsedDevice,
+    errors::{Error, Result},
+    picker,
+};
+use crate::cli::SwitchSelector;
+
+/// How many similar switch-ids are suggested if a switch does not exist
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Resolves the selector against the user's personal switches
+/// Switches can be selected by their ID or their name (case-insensitive)
+/// Switches which were selected explicitly are always included, all other selectors only include switches with power
+/// Switches selected explicitly which are not personal switches are looked up in all switches the user can access
+/// If nothing was selected and the CLI runs in a terminal, the user may pick switches interactively
+pub async fn resolve(client: &Client, selector: &SwitchSelector) -> Result<Vec<ParsedDevice>> {
+    let mut devices: Vec<ParsedDevice> = match client.personal_switches().await {
+        Ok(response) => response.into_iter().map(ParsedDevice::from).collect(),
+        Err(err) => return Err(Error::GetDevices(err)),
+    };
+
+    let mut queries = selector.switch_ids.clone();
+    if queries.is_empty()
+        && !selector.all
+        && selector.room.is_empty()
+        && selector.name_glob.is_none()
+    {
+        if !io::stdin().is_terminal() || !io::stdout().is_terminal() {
+            return Err(Error::NoSelection);
+        }
+        let candidates: Vec<ParsedDevice> = devices
+            .iter()
+            .filter(|device| device.power.is_some())
+            .cloned()
+            .collect();
+        queries = picker::pick(&candidates)?;
+    }
+
+    let missing: Vec<&String> = queries
+        .iter()
+        .filter(|query| !devices.iter().any(|device| matches(device, query)))
+        .collect();
+    if !missing.is_empty() {
+        debug!("Looking up non-personal switches...");
+        match client.all_switches().await {
+            Ok(response) => devices.extend(
+                response
+                    .into_iter()
+                    .map(ParsedDevice::from)
+                    .filter(|device| missing.iter().any(|query| matches(device, query))),
+            ),
+            Err(err) => debug!("Could not fetch all switches: {err}"),
+        }
+    }
+
+    // Every explicitly selected switch must exist
+    if let Some(unknown) = queries
+        .iter()
+        .find(|query| !devices.iter().any(|device| matches(device, query)))
+    {
+        return Err(Error::SwitchNotFound {
+            query: unknown.to_string(),
+            suggestions: suggest(&devices, unknown),
+        });
+    }
+
+    let pattern = match &selector.name_glob {
+        Some(glob) => match Pattern::new(glob) {
+            Ok(pattern) => Some(pattern),
+            Err(err) => return Err(Error::InvalidGlob(format!("{glob}: {err}"))),
+        },
+        None => None,
+    };
+
+    let selected: Vec<ParsedDevice> = devices
+        .into_iter()
+        .filter(|device| {
+            queries.iter().any(|query| matches(device, query))
+                || device.power.is_some()
+                    && (selector.all
+                        || selector.room.contains(&device.room_id)
+                        || pattern.as_ref().is_some_and(|pattern| {
+                            pattern.matches(&device.id) || pattern.matches(&device.name)
+                        }))
+        })
+        .filter(|device| !selector.exclude.iter().any(|query| matches(device, query)))
+        .filter(
+            |device| match (selector.only_on, selector.only_off, &device.power) {
+                (false, false, _) => true,
+                (true, _, Some(power)) => power.status,
+                (_, true, Some(power)) => !power.status,
+                (_, _, None) => false,
+            },
+        )
+        .collect();
+
+    debug!(
+        "Selected {} switch(es): {}",
+        selected.len(),
+        selected
+            .iter()
+            .map(|device| device.id.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    );
+    Ok(selected)
+}
+
+/// Whether the device's ID or name equals the query
+fn matches(device: &ParsedDevice, query: &str) -> bool {
+    device.id == query || device.name.eq_ignore_ascii_case(query)
+}
+
+/// Returns the IDs of the switches which are most similar to the query
+fn suggest(devices: &[ParsedDevice], query: &str) -> Vec<String> {
+    let query = query.to_lowercase();
+    // Suggestions which differ too much from the query are rather confusing than helpful
+    let threshold = (query.chars().count() / 3).max(2);
+    let mut candidates: Vec<(usize, &str)> = devices
+        .iter()
+        .map(|device| (distance(&query, device), device.id.as_str()))
+        .filter(|(distance, _)| *distance <= threshold)
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, id)| id.to_string())
+        .collect()
+}
+
+/// The edit distance between the lowercase query and the device's ID or name, whichever is closer
+pub fn distance(query: &str, device: &ParsedDevice) -> usize {
+    levenshtein(query, &device.id.to_lowercase())
+        .min(levenshtein(query, &device.name.to_lowercase()))
+}
+
+fn levenshtein(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1; right.len() + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            current[j + 1] = (previous[j] + usize::from(left_char != *right_char))
+                .min(previous[j + 1] + 1)
+                .min(current[j] + 1);
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
@@ -0,0 +1,158 @@
+use std::{
+    collections::HashMap,
+    slice,
+    time::{Duration, Instant},
+};
+
+use log::{debug, info, warn};
+use smarthome_sdk_rs::Client;
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    draw::ParsedDevice,
+    errors::{Error, Result},
+    select,
+};
+use crate::cli::SwitchSelector;
+
+#[derive(Tabled)]
+struct TableMismatch {
+    #[tabled(rename = "Switch")]
+    switch_id: String,
+    #[tabled(display_with("Self::display_power"), rename = "Expected")]
+    expected: Option<bool>,
+    #[tabled(display_with("Self::display_power"), rename = "Actual")]
+    actual: Option<bool>,
+}
+
+impl TableMismatch {
+    fn display_power(power_on: &Option<bool>) -> String {
+        match power_on {
+            Some(true) => "\x1b[1;32mON\x1b[1;0m".to_string(),
+            Some(false) => "\x1b[1;31mOFF\x1b[1;0m".to_string(),
+            None => "\x1b[1;30mN/A\x1b[1;0m".to_string(),
+        }
+    }
+}
+
+/// Reads the current power states of the given switches, `None` if a switch does not report its power
+/// Switches which are not personal switches are looked up in all switches the user can access
+async fn states(client: &Client, ids: &[String]) -> Result<HashMap<String, Option<bool>>> {
+    let collect = |devices: Vec<_>| {
+        devices
+            .into_iter()
+            .map(ParsedDevice::from)
+            .filter(|device| ids.contains(&device.id))
+            .map(|device| (device.id, device.power.map(|power| power.status)))
+            .collect::<Vec<(String, Option<bool>)>>()
+    };
+
+    let mut states: HashMap<String, Option<bool>> = collect(
+        client
+            .personal_switches()
+            .await
+            .map_err(Error::GetDevices)?,
+    )
+    .into_iter()
+    .collect();
+    if ids.iter().any(|id| !states.contains_key(id)) {
+        match client.all_switches().await {
+            Ok(response) => states.extend(collect(response)),
+            Err(err) => debug!("Could not fetch all switches: {err}"),
+        }
+    }
+    Ok(states)
+}
+
+/// Re-reads the power states of the switches and reports every switch which is not in its target state
+pub async fn verify(client: &Client, targets: &[(String, bool)]) -> Result<()> {
+    let ids: Vec<String> = targets.iter().map(|(id, _)| id.clone()).collect();
+    let states = states(client, &ids).await?;
+
+    let mismatches: Vec<TableMismatch> = targets
+        .iter()
+        .filter_map(|(switch_id, expected)| {
+            let actual = states.get(switch_id).copied().flatten();
+            (actual != Some(*expected)).then(|| TableMismatch {
+                switch_id: switch_id.clone(),
+                expected: Some(*expected),
+                actual,
+            })
+        })
+        .collect();
+    if mismatches.is_empty() {
+        info!(
+            "Verified that {} switch(es) reached their target state",
+            targets.len()
+        );
+        return Ok(());
+    }
+
+    let mismatched = mismatches.len();
+    let mut table = Table::new(mismatches);
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+    Err(Error::VerificationFailed {
+        mismatched,
+        total: targets.len(),
+    })
+}
+
+/// Polls the power state of a switch until it reaches the target state or the timeout elapses
+/// Errors while polling are only reported, as the server or the hardware may be unavailable temporarily
+pub async fn wait(
+    client: &Client,
+    query: &str,
+    power_on: bool,
+    timeout: Duration,
+    interval: Duration,
+) -> Result<()> {
+    let selector = SwitchSelector {
+        switch_ids: vec![query.to_string()],
+        ..SwitchSelector::default()
+    };
+    let Some(device) = select::resolve(client, &selector).await?.into_iter().next() else {
+        return Err(Error::NoSelection);
+    };
+    if device.power.is_none() {
+        return Err(Error::NoPowerCapability(device.id));
+    }
+
+    let deadline = Instant::now()
+        .checked_add(timeout)
+        .ok_or(Error::InvalidTimespan)?;
+    let mut current = device.power.map(|power| power.status);
+    loop {
+        if current == Some(power_on) {
+            info!(
+                "The switch `{}` is {}",
+                device.id,
+                if power_on { "on" } else { "off" }
+            );
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(Error::WaitTimeout {
+                switch_id: device.id,
+                power_on,
+                timeout,
+            });
+        }
+        debug!("Waiting for `{}` to change its power state...", device.id);
+        tokio::time::sleep(interval.min(deadline - now)).await;
+
+        match states(client, slice::from_ref(&device.id)).await {
+            Ok(states) => current = states.get(&device.id).copied().flatten(),
+            Err(err) => warn!("Could not read the power state of `{}`: {err}", device.id),
+        }
+    }
+}
@@ -0,0 +1,433 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, Duration, Local, TimeZone, Timelike};
+use smarthome_sdk_rs::{Client, PowerDrawPoint};
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    errors::{Error, Result},
+    tariff,
+};
+use crate::{cli::Resolution, config::PowerConfig};
+
+/// The percentiles of the on-power draw which are displayed
+const PERCENTILES: [usize; 3] = [50, 90, 99];
+
+/// The timespan which is analyzed
+pub struct Timespan {
+    pub since: DateTime<Local>,
+    pub until: DateTime<Local>,
+}
+
+impl Timespan {
+    /// The last 24 hours, which is the timespan the server returns by default
+    pub fn last_day() -> Self {
+        let until = Local::now();
+        Self {
+            since: until - Duration::hours(24),
+            until,
+        }
+    }
+
+    /// Whether this timespan is not covered by the server's data of the last 24 hours
+    pub fn requires_all_data(&self) -> bool {
+        // A minute of tolerance so that the default timespan never requires all data
+        self.since < Local::now() - Duration::hours(24) - Duration::minutes(1)
+    }
+
+    pub fn contains(&self, point: &PowerDrawPoint) -> bool {
+        (self.since.timestamp_millis()..=self.until.timestamp_millis())
+            .contains(&(point.time as i64))
+    }
+}
+
+/// Fetches the power draw data of the timespan, all data is only fetched if the timespan requires it
+pub async fn history(client: &Client, timespan: &Timespan) -> Result<Vec<PowerDrawPoint>> {
+    match client.power_usage(timespan.requires_all_data()).await {
+        Ok(response) => Ok(response
+            .into_iter()
+            .filter(|point| timespan.contains(point))
+            .collect()),
+        Err(err) => Err(Error::GetPowerDrawData(err)),
+    }
+}
+
+pub struct Summary {
+    pub energy: Energy,
+    pub average: f64,
+    pub minimum: usize,
+    pub peak: usize,
+    /// Pairs of percentile and on-power draw in watts
+    pub percentiles: Vec<(usize, usize)>,
+}
+
+#[derive(Tabled)]
+struct TableBucket {
+    #[tabled(rename = "Period")]
+    period: String,
+    #[tabled(rename = "Used (kWh)")]
+    kwh: String,
+    #[tabled(rename = "Cost")]
+    cost: String,
+    #[tabled(rename = "Average (W)")]
+    average: String,
+    #[tabled(rename = "Minimum (W)")]
+    minimum: usize,
+    #[tabled(rename = "Peak (W)")]
+    peak: usize,
+}
+
+/// Summarizes the on-power draw of the data, `None` if there is no data at all
+pub fn summarize(data: &[PowerDrawPoint]) -> Option<Summary> {
+    let mut watts: Vec<usize> = data.iter().map(|point| point.on.watts).collect();
+    watts.sort_unstable();
+    let (Some(minimum), Some(peak)) = (watts.first(), watts.last()) else {
+        return None;
+    };
+
+    let (intervals, _) = intervals(data);
+    Some(Summary {
+        energy: energy(data).ok()?,
+        average: average_watts(
+            intervals.iter().map(Interval::on_kwh).sum(),
+            intervals.iter().map(|interval| interval.duration_ms).sum(),
+            &watts,
+        ),
+        minimum: *minimum,
+        peak: *peak,
+        percentiles: PERCENTILES
+            .iter()
+            .map(|percentile| (*percentile, nearest_rank(&watts, *percentile)))
+            .collect(),
+    })
+}
+
+/// The time-weighted average power draw, the energy divided by the duration which it covers
+/// If the data does not cover any time, for example a single point, the mean of the points is used instead
+fn average_watts(kwh: f64, duration_ms: u64, watts: &[usize]) -> f64 {
+    match duration_ms {
+        0 => watts.iter().sum::<usize>() as f64 / watts.len().max(1) as f64,
+        duration_ms => kwh * (1000.0 * 60.0 * 60.0 * 1000.0) / duration_ms as f64,
+    }
+}
+
+/// Returns the value at the given percentile of the sorted data using the nearest-rank method
+fn nearest_rank(sorted: &[usize], percentile: usize) -> usize {
+    let rank = (percentile * sorted.len()).div_ceil(100);
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Energy used during the timespan of some power draw data
+#[derive(Debug, PartialEq)]
+pub struct Energy {
+    /// Energy drawn by switches which were turned on
+    pub on_kwh: f64,
+    /// Energy which switches that were turned off would have drawn
+    pub off_kwh: f64,
+    /// Periods without data, which are not accounted for
+    pub gaps: Vec<Gap>,
+}
+
+impl Energy {
+    pub fn total_kwh(&self) -> f64 {
+        self.on_kwh + self.off_kwh
+    }
+}
+
+/// A period between two consecutive points which is too long to be a regular sampling interval
+#[derive(Debug, PartialEq, Eq)]
+pub struct Gap {
+    /// Unix timestamps in milliseconds
+    pub start: u64,
+    pub end: u64,
+}
+
+impl Gap {
+    pub fn duration_ms(&self) -> u64 {
+        self.end - self.start
+    }
+}
+
+/// Intervals are only considered as a gap if they exceed this duration
+const MIN_GAP_MS: u64 = 15 * 60 * 1000;
+/// Intervals which are this many times longer than the median interval are considered as a gap
+const GAP_FACTOR: u64 = 3;
+
+/// Two consecutive points between which the power draw can be integrated
+pub struct Interval<'data> {
+    pub start: &'data PowerDrawPoint,
+    pub end: &'data PowerDrawPoint,
+    pub duration_ms: u64,
+}
+
+impl Interval<'_> {
+    pub fn on_kwh(&self) -> f64 {
+        trapezoid_kwh(self.start.on.watts, self.end.on.watts, self.duration_ms)
+    }
+
+    pub fn off_kwh(&self) -> f64 {
+        trapezoid_kwh(self.start.off.watts, self.end.off.watts, self.duration_ms)
+    }
+
+    /// Unix timestamp in milliseconds
+    pub fn midpoint(&self) -> u64 {
+        self.start.time + self.duration_ms / 2
+    }
+}
+
+/// Splits the data into intervals which can be integrated and gaps in which data is missing
+/// Intervals between points which are out of order are neither
+pub fn intervals(data: &[PowerDrawPoint]) -> (Vec<Interval<'_>>, Vec<Gap>) {
+    let threshold = gap_threshold(data);
+    let mut intervals = vec![];
+    let mut gaps = vec![];
+    for window in data.windows(2) {
+        let (start, end) = (&window[0], &window[1]);
+        match end.time.checked_sub(start.time) {
+            Some(duration_ms) if duration_ms > threshold => gaps.push(Gap {
+                start: start.time,
+                end: end.time,
+            }),
+            Some(duration_ms) => intervals.push(Interval {
+                start,
+                end,
+                duration_ms,
+            }),
+            None => {}
+        }
+    }
+    (intervals, gaps)
+}
+
+/// Integrates the power draw over time using the trapezoidal rule with millisecond precision
+/// Gaps in the data are skipped, because the power draw during them is unknown
+pub fn energy(data: &[PowerDrawPoint]) -> Result<Energy> {
+    if data.is_empty() {
+        return Err(Error::NotEnoughPowerDrawData);
+    }
+    let (intervals, gaps) = intervals(data);
+    Ok(Energy {
+        on_kwh: intervals.iter().map(Interval::on_kwh).sum(),
+        off_kwh: intervals.iter().map(Interval::off_kwh).sum(),
+        gaps,
+    })
+}
+
+/// The energy of a linear transition between two power draws over the duration (in milliseconds)
+fn trapezoid_kwh(start_watts: usize, end_watts: usize, duration_ms: u64) -> f64 {
+    let average_watts = (start_watts + end_watts) as f64 / 2.0;
+    average_watts * duration_ms as f64 / (1000.0 * 60.0 * 60.0 * 1000.0)
+}
+
+/// Returns the duration (in milliseconds) above which an interval is considered as a gap
+fn gap_threshold(data: &[PowerDrawPoint]) -> u64 {
+    let mut intervals: Vec<u64> = data
+        .windows(2)
+        .filter_map(|window| window[1].time.checked_sub(window[0].time))
+        .collect();
+    intervals.sort_unstable();
+    match intervals.get(intervals.len() / 2) {
+        Some(median) => (median * GAP_FACTOR).max(MIN_GAP_MS),
+        None => MIN_GAP_MS,
+    }
+}
+
+/// Prints a table which aggregates the data per hour or per day (in local time)
+pub fn print_aggregated(data: &[PowerDrawPoint], resolution: Resolution, config: &PowerConfig) {
+    // Each bucket holds the energy, its cost, the covered duration and the on-watts of all points which belong to it
+    let mut buckets: BTreeMap<DateTime<Local>, (f64, f64, u64, Vec<usize>)> = BTreeMap::new();
+    for point in data {
+        if let Some(start) = bucket_start(point.time, resolution) {
+            buckets.entry(start).or_default().3.push(point.on.watts);
+        }
+    }
+    // Every interval is accounted to the bucket of its later point
+    for interval in intervals(data).0 {
+        if let Some(bucket) =
+            bucket_start(interval.end.time, resolution).and_then(|start| buckets.get_mut(&start))
+        {
+            bucket.0 += interval.on_kwh();
+            bucket.1 += interval.on_kwh() * tariff::price_at(config, interval.midpoint());
+            bucket.2 += interval.duration_ms;
+        }
+    }
+
+    let mut table = Table::new(buckets.into_iter().map(
+        |(start, (kwh, cost, duration_ms, watts))| TableBucket {
+            period: match resolution {
+                Resolution::Hour => start.format("%Y-%m-%d %H:00").to_string(),
+                Resolution::Day => start.format("%Y-%m-%d").to_string(),
+            },
+            kwh: format!("{kwh:.3}"),
+            cost: format!("{cost:.2} {}", config.currency),
+            average: format!("{:.0}", average_watts(kwh, duration_ms, &watts)),
+            minimum: watts.iter().copied().min().unwrap_or_default(),
+            peak: watts.iter().copied().max().unwrap_or_default(),
+        },
+    ));
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+}
+
+/// Returns the start of the hour or day which contains the timestamp (in milliseconds)
+fn bucket_start(time: u64, resolution: Resolution) -> Option<DateTime<Local>> {
+    let time = Local.timestamp_millis_opt(time as i64).single()?;
+    let hour = time.with_minute(0)?.with_second(0)?.with_nanosecond(0)?;
+    match resolution {
+        Resolution::Hour => Some(hour),
+        Resolution::Day => Local
+            .from_local_datetime(&time.date_naive().and_hms_opt(0, 0, 0)?)
+            .earliest(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::power::fixtures::point;
+
+    const MINUTE: u64 = 60 * 1000;
+    const HOUR: u64 = 60 * MINUTE;
+
+    fn integrate(data: &[PowerDrawPoint]) -> Energy {
+        match energy(data) {
+            Ok(energy) => energy,
+            Err(err) => panic!("{err}"),
+        }
+    }
+
+    fn assert_close(left: f64, right: f64) {
+        assert!((left - right).abs() < 1e-9, "{left} != {right}");
+    }
+
+    #[test]
+    fn empty_data() {
+        assert!(energy(&[]).is_err());
+    }
+
+    #[test]
+    fn single_point() {
+        let energy = integrate(&[point(0, 100, 50)]);
+        assert_close(energy.on_kwh, 0.0);
+        assert_close(energy.off_kwh, 0.0);
+        assert!(energy.gaps.is_empty());
+    }
+
+    #[test]
+    fn constant_draw() {
+        let data: Vec<PowerDrawPoint> =
+            (0..=4).map(|i| point(i * 15 * MINUTE, 1000, 200)).collect();
+        let energy = integrate(&data);
+        assert_close(energy.on_kwh, 1.0);
+        assert_close(energy.off_kwh, 0.2);
+        assert_close(energy.total_kwh(), 1.2);
+    }
+
+    #[test]
+    fn linear_transition() {
+        // 0 W to 1000 W over one hour is 0.5 kWh, while the old method accounted for 1 kWh
+        let energy = integrate(&[point(0, 0, 1000), point(HOUR, 1000, 0)]);
+        assert_close(energy.on_kwh, 0.5);
+        assert_close(energy.off_kwh, 0.5);
+    }
+
+    #[test]
+    fn irregular_sampling_with_millisecond_precision() {
+        let data = [
+            point(0, 600, 0),
+            point(90 * 1000 + 500, 600, 0),
+            point(10 * MINUTE, 600, 0),
+            point(10 * MINUTE + 1, 600, 0),
+            point(12 * MINUTE + 30 * 1000, 600, 0),
+        ];
+        let energy = integrate(&data);
+        // 600 W for 12.5 minutes, none of the sub-minute intervals are truncated
+        assert_close(energy.on_kwh, 0.6 * 12.5 / 60.0);
+        assert!(energy.gaps.is_empty());
+    }
+
+    #[test]
+    fn gaps_are_detected_and_skipped() {
+        let mut data: Vec<PowerDrawPoint> =
+            (0..=4).map(|i| point(i * 15 * MINUTE, 400, 0)).collect();
+        data.extend((0..=4).map(|i| point(5 * HOUR + i * 15 * MINUTE, 400, 0)));
+        let energy = integrate(&data);
+        assert_close(energy.on_kwh, 0.8);
+        assert_eq!(
+            energy.gaps,
+            vec![Gap {
+                start: HOUR,
+                end: 5 * HOUR
+            }]
+        );
+        assert_eq!(energy.gaps[0].duration_ms(), 4 * HOUR);
+    }
+
+    #[test]
+    fn out_of_order_points_are_ignored() {
+        let energy = integrate(&[
+            point(HOUR, 100, 0),
+            point(0, 100, 0),
+            point(HOUR / 4, 100, 0),
+        ]);
+        assert_close(energy.on_kwh, 0.025);
+    }
+
+    #[test]
+    fn summarize_empty_data() {
+        assert!(summarize(&[]).is_none());
+    }
+
+    #[test]
+    fn summarize_single_point() {
+        let Some(summary) = summarize(&[point(0, 250, 50)]) else {
+            panic!("A single point must be summarized");
+        };
+        assert_close(summary.average, 250.0);
+        assert_eq!((summary.minimum, summary.peak), (250, 250));
+        assert!(summary.percentiles.iter().all(|(_, watts)| *watts == 250));
+        assert_close(summary.energy.total_kwh(), 0.0);
+    }
+
+    #[test]
+    fn summarize_all_off() {
+        let data: Vec<PowerDrawPoint> = (0..=4).map(|i| point(i * 15 * MINUTE, 0, 500)).collect();
+        let Some(summary) = summarize(&data) else {
+            panic!("The data must be summarized");
+        };
+        assert_close(summary.average, 0.0);
+        assert_eq!((summary.minimum, summary.peak), (0, 0));
+        assert_close(summary.energy.on_kwh, 0.0);
+        assert_close(summary.energy.off_kwh, 0.5);
+    }
+
+    #[test]
+    fn summarize_weights_the_average_by_time() {
+        // The draw rises to 1000 W within the first minute and stays there for the rest of the hour
+        let data = [
+            point(0, 0, 0),
+            point(MINUTE, 1000, 0),
+            point(60 * MINUTE, 1000, 0),
+        ];
+        let Some(summary) = summarize(&data) else {
+            panic!("The data must be summarized");
+        };
+        assert_close(summary.average, (500.0 + 59.0 * 1000.0) / 60.0);
+        assert_close(summary.energy.on_kwh, summary.average / 1000.0);
+    }
+
+    #[test]
+    fn percentiles() {
+        let sorted: Vec<usize> = (1..=100).collect();
+        assert_eq!(nearest_rank(&sorted, 50), 50);
+        assert_eq!(nearest_rank(&sorted, 99), 99);
+        assert_eq!(nearest_rank(&[7], 90), 7);
+    }
+}
@@ -1,54 +1,223 @@
-use log::{debug, trace};
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::Utc;
+use futures::{stream, StreamExt};
+use log::{debug, trace, warn};
 use reqwest::StatusCode;
-use smarthome_sdk_rs::{Client, DeviceCapability, Error as SdkError};
+use smarthome_sdk_rs::{Client, Error as SdkError};
 use tabled::{
     settings::{format::Format, object::Rows, Modify, Style},
-    Table,
+    Table, Tabled,
 };
 
 use crate::power::draw::{ParsedDevice, TableDevice};
 
-use super::errors::Error;
+use super::{
+    errors::Error,
+    journal::{self, Batch, Change},
+    state,
+};
 
-pub async fn toggle_power(client: &Client, switch_ids: &[String]) -> Result<(), Error> {
-    let switches = match client.personal_switches().await {
-        Ok(response) => response,
-        Err(err) => return Err(Error::GetDevices(err)),
-    };
-    for switch in switch_ids.iter() {
-        let old_state = match switches.iter().find(|sw| sw.shallow.id == *switch) {
-            Some(switch) => {
-                let has_power_capability = switch
-                    .extractions
-                    .config
-                    .capabilities
-                    .contains(&DeviceCapability::Power);
-
-                match (has_power_capability, &switch.extractions.power_information) {
-                    (_, None) | (false, _) => {
-                        return Err(Error::InvalidSwitch(
-                            "device does not support power".to_string(),
-                        ));
-                    }
-                    (true, Some(power)) => power.state,
-                }
-            }
-            None => return Err(Error::InvalidSwitch(switch.clone())),
-        };
-        set_power_helper(client, switch, !old_state).await?
+#[derive(Tabled)]
+struct TableSwitchResult {
+    #[tabled(rename = "Switch")]
+    switch_id: String,
+    #[tabled(display_with("Self::display_power"), rename = "Power")]
+    power_on: bool,
+    #[tabled(rename = "Result")]
+    result: String,
+}
+
+impl TableSwitchResult {
+    fn display_power(power_on: &bool) -> String {
+        match power_on {
+            true => "\x1b[1;32mON\x1b[1;0m".to_string(),
+            false => "\x1b[1;31mOFF\x1b[1;0m".to_string(),
+        }
     }
-    Ok(())
+
+    fn display_result(result: &Result<(), Error>) -> String {
+        match result {
+            Ok(_) => "\x1b[1;32mOK\x1b[1;0m".to_string(),
+            Err(Error::InvalidSwitch(_)) => "\x1b[1;31mINVALID\x1b[1;0m".to_string(),
+            Err(Error::PermissionDenied(_)) => "\x1b[1;31mFORBIDDEN\x1b[1;0m".to_string(),
+            Err(Error::ServerError) => "\x1b[1;31mSERVER ERROR\x1b[1;0m".to_string(),
+            Err(err) => format!("\x1b[1;31mERROR\x1b[1;0m: {err}"),
+        }
+    }
+}
+
+/// A single power change which is performed by `execute_jobs`
+pub struct PowerJob {
+    pub switch_id: String,
+    /// The state of the switch before the change, `None` if it is unknown
+    pub previous: Option<bool>,
+    pub power_on: bool,
+}
+
+/// The outcome of executing power jobs
+pub struct Executed {
+    /// The IDs of the switches which were switched successfully
+    pub switched: Vec<String>,
+    pub result: Result<(), Error>,
+}
+
+/// Toggles the power state of every device
+/// In group mode, all devices are switched to a common state: on if any device is off, otherwise off
+pub async fn toggle_power(
+    client: &Client,
+    devices: &[ParsedDevice],
+    group: bool,
+    concurrency: usize,
+    verify: bool,
+) -> Result<(), Error> {
+    let mut states = Vec::with_capacity(devices.len());
+    for device in devices {
+        match &device.power {
+            Some(power) => states.push((device.id.clone(), power.status)),
+            None => return Err(Error::NoPowerCapability(device.id.clone())),
+        }
+    }
+
+    let group_state = states.iter().any(|(_, state)| !state);
+    let jobs = states
+        .into_iter()
+        .map(|(switch_id, state)| PowerJob {
+            switch_id,
+            previous: Some(state),
+            power_on: match group {
+                true => group_state,
+                false => !state,
+            },
+        })
+        .collect();
+    execute_and_verify(client, jobs, concurrency, "power toggle", verify)
+        .await
+        .result
 }
 
 pub async fn set_power(
     client: &Client,
-    switch_ids: &Vec<String>,
+    devices: &[ParsedDevice],
     power_on: bool,
-) -> Result<(), Error> {
-    for switch in switch_ids {
-        set_power_helper(client, switch, power_on).await?;
+    concurrency: usize,
+    verify: bool,
+) -> Executed {
+    execute_and_verify(
+        client,
+        devices
+            .iter()
+            .map(|device| PowerJob {
+                switch_id: device.id.clone(),
+                previous: device.power.as_ref().map(|power| power.status),
+                power_on,
+            })
+            .collect(),
+        concurrency,
+        if power_on { "power on" } else { "power off" },
+        verify,
+    )
+    .await
+}
+
+/// Executes the jobs and records them in the journal
+/// If requested, the power states are re-read afterwards, as some hardware fails without reporting an error
+async fn execute_and_verify(
+    client: &Client,
+    jobs: Vec<PowerJob>,
+    concurrency: usize,
+    journal_label: &str,
+    verify: bool,
+) -> Executed {
+    let targets: Vec<(String, bool)> = jobs
+        .iter()
+        .map(|job| (job.switch_id.clone(), job.power_on))
+        .collect();
+    let executed = execute_jobs(client, jobs, concurrency, Some(journal_label)).await;
+    if !verify {
+        return executed;
+    }
+    // Switching errors take precedence, but the mismatches are still reported
+    let verified = state::verify(client, &targets).await;
+    Executed {
+        result: executed.result.and(verified),
+        ..executed
+    }
+}
+
+/// Switches every job's switch to its target state, at most `concurrency` switches are switched at once
+/// Failures do not abort the remaining jobs, instead every result is reported in a table
+/// If a journal label is given, the successful changes are recorded in the journal so that they can be undone
+pub async fn execute_jobs(
+    client: &Client,
+    jobs: Vec<PowerJob>,
+    concurrency: usize,
+    journal_label: Option<&str>,
+) -> Executed {
+    let mut results: Vec<(usize, PowerJob, Result<(), Error>)> =
+        stream::iter(jobs.into_iter().enumerate())
+            .map(|(index, job)| async move {
+                let result = set_power_helper(client, &job.switch_id, job.power_on).await;
+                (index, job, result)
+            })
+            .buffer_unordered(concurrency.max(1))
+            .collect()
+            .await;
+    results.sort_by_key(|(index, ..)| *index);
+
+    let total = results.len();
+    let failed = results
+        .iter()
+        .filter(|(.., result)| result.is_err())
+        .count();
+
+    let mut table = Table::new(results.iter().map(|(_, job, result)| TableSwitchResult {
+        switch_id: job.switch_id.clone(),
+        power_on: job.power_on,
+        result: TableSwitchResult::display_result(result),
+    }));
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+
+    let changes: Vec<Change> = results
+        .into_iter()
+        .filter(|(.., result)| result.is_ok())
+        .map(|(_, job, _)| Change {
+            switch_id: job.switch_id,
+            previous: job.previous,
+            new: job.power_on,
+        })
+        .collect();
+    let switched = changes
+        .iter()
+        .map(|change| change.switch_id.clone())
+        .collect();
+
+    if let Some(label) = journal_label {
+        if !changes.is_empty() {
+            // A broken journal should never prevent switching
+            if let Err(err) = journal::record(Batch {
+                time: Utc::now().timestamp_millis(),
+                server: client.smarthome_url.to_string(),
+                label: label.to_string(),
+                changes,
+            }) {
+                warn!("Could not record power changes in the journal: {err}");
+            }
+        }
+    }
+
+    Executed {
+        switched,
+        result: match failed {
+            0 => Ok(()),
+            failed => Err(Error::SwitchingFailed { failed, total }),
+        },
     }
-    Ok(())
 }
 
 pub async fn set_power_helper(
@@ -80,7 +249,13 @@ pub async fn set_power_helper(
     }
 }
 
-pub async fn switch_list(client: &Client, show_all: bool) -> Result<(), Error> {
+/// Lists the user's switches or all switches, optionally filtered by room or grouped by room
+pub async fn switch_list(
+    client: &Client,
+    show_all: bool,
+    by_room: bool,
+    rooms: &[String],
+) -> Result<(), Error> {
     let switches = match if show_all {
         client.all_switches().await
     } else {
@@ -89,16 +264,60 @@ pub async fn switch_list(client: &Client, show_all: bool) -> Result<(), Error> {
         Ok(response) => response,
         Err(err) => return Err(Error::GetDevices(err)),
     };
-    let mut table = Table::new(
-        switches
+    let devices: Vec<ParsedDevice> = switches
+        .into_iter()
+        .map(ParsedDevice::from)
+        .filter(|device| rooms.is_empty() || rooms.contains(&device.room_id))
+        .collect();
+
+    if !by_room {
+        print_devices(devices);
+        return Ok(());
+    }
+
+    // Room names are only cosmetic, the room IDs are used if they are unavailable
+    let names: HashMap<String, String> = match client.personal_rooms().await {
+        Ok(rooms) => rooms
             .into_iter()
-            .map(|f| TableDevice::from(ParsedDevice::from(f))),
-    );
+            .map(|room| (room.data.id, room.data.name))
+            .collect(),
+        Err(err) => {
+            warn!("Could not get room names: {err}");
+            HashMap::new()
+        }
+    };
+
+    let mut grouped: BTreeMap<String, Vec<ParsedDevice>> = BTreeMap::new();
+    for device in devices {
+        grouped
+            .entry(device.room_id.clone())
+            .or_default()
+            .push(device);
+    }
+    for (room_id, devices) in grouped {
+        let (on, off, active_watts) = devices
+            .iter()
+            .filter_map(|device| device.power.as_ref())
+            .fold((0, 0, 0), |(on, off, watts), power| match power.status {
+                true => (on + 1, off, watts + power.watts),
+                false => (on, off + 1, watts),
+            });
+        match names.get(&room_id) {
+            Some(name) => println!("=== {name} ({room_id}) ==="),
+            None => println!("=== {room_id} ==="),
+        }
+        print_devices(devices);
+        println!("  {on} on, {off} off, {active_watts} W active\n");
+    }
+    Ok(())
+}
+
+fn print_devices(devices: Vec<ParsedDevice>) {
+    let mut table = Table::new(devices.into_iter().map(TableDevice::from));
     println!(
         "{}",
         table.with(Style::modern().remove_horizontal()).with(
             Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
         )
     );
-    Ok(())
 }
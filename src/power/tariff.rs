@@ -0,0 +1,222 @@
+use chrono::{Datelike, Local, TimeZone, Timelike, Weekday};
+use smarthome_sdk_rs::PowerDrawPoint;
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::stats::{self, Timespan};
+use crate::config::{PowerConfig, Tariff, TariffDay};
+
+/// The name under which energy is listed if no tariff applies
+const DEFAULT_TARIFF: &str = "Default";
+
+/// The cost of the energy used during a timespan, split by tariff
+pub struct CostReport {
+    pub tariffs: Vec<TariffCost>,
+    /// The standing charge for the whole timespan
+    pub standing_charge: f64,
+}
+
+pub struct TariffCost {
+    pub name: String,
+    pub cost_per_kwh: f64,
+    pub kwh: f64,
+    pub cost: f64,
+}
+
+impl CostReport {
+    pub fn total(&self) -> f64 {
+        self.tariffs.iter().map(|tariff| tariff.cost).sum::<f64>() + self.standing_charge
+    }
+}
+
+#[derive(Tabled)]
+struct TableTariffCost {
+    #[tabled(rename = "Tariff")]
+    name: String,
+    #[tabled(rename = "Price (per kWh)")]
+    price: String,
+    #[tabled(rename = "Used (kWh)")]
+    kwh: String,
+    #[tabled(rename = "Cost")]
+    cost: String,
+}
+
+/// Returns the index of the first tariff which applies at the given Unix timestamp (in milliseconds)
+/// Returns `None` if the default price applies
+fn tariff_at(config: &PowerConfig, time: u64) -> Option<usize> {
+    let time = Local.timestamp_millis_opt(time as i64).single()?;
+    let hour = time.hour() as u8;
+    config
+        .tariffs
+        .iter()
+        .position(|tariff| applies(tariff, time.weekday(), hour))
+}
+
+/// Whether the tariff applies during the hour of the weekday
+/// The hours after midnight of a tariff which spans midnight belong to the day on which it started
+fn applies(tariff: &Tariff, weekday: Weekday, hour: u8) -> bool {
+    let on_day =
+        |weekday: Weekday| tariff.days.is_empty() || tariff.days.contains(&tariff_day(weekday));
+    match tariff.start_hour < tariff.end_hour {
+        true => (tariff.start_hour..tariff.end_hour).contains(&hour) && on_day(weekday),
+        // The tariff spans midnight
+        false if hour >= tariff.start_hour => on_day(weekday),
+        false => hour < tariff.end_hour && on_day(weekday.pred()),
+    }
+}
+
+fn tariff_day(weekday: Weekday) -> TariffDay {
+    match weekday {
+        Weekday::Mon => TariffDay::Mon,
+        Weekday::Tue => TariffDay::Tue,
+        Weekday::Wed => TariffDay::Wed,
+        Weekday::Thu => TariffDay::Thu,
+        Weekday::Fri => TariffDay::Fri,
+        Weekday::Sat => TariffDay::Sat,
+        Weekday::Sun => TariffDay::Sun,
+    }
+}
+
+/// Returns the price per kWh at the given Unix timestamp (in milliseconds)
+pub fn price_at(config: &PowerConfig, time: u64) -> f64 {
+    tariff_at(config, time).map_or(config.cost_per_kwh, |index| {
+        config.tariffs[index].cost_per_kwh
+    })
+}
+
+/// Computes the cost of the used (on) energy, every interval is priced at its midpoint
+pub fn report(config: &PowerConfig, data: &[PowerDrawPoint], timespan: &Timespan) -> CostReport {
+    let mut tariffs: Vec<TariffCost> = config
+        .tariffs
+        .iter()
+        .map(|tariff| TariffCost {
+            name: tariff.name.clone(),
+            cost_per_kwh: tariff.cost_per_kwh,
+            kwh: 0.0,
+            cost: 0.0,
+        })
+        .chain([TariffCost {
+            name: DEFAULT_TARIFF.to_string(),
+            cost_per_kwh: config.cost_per_kwh,
+            kwh: 0.0,
+            cost: 0.0,
+        }])
+        .collect();
+
+    for interval in stats::intervals(data).0 {
+        // The default tariff is always the last one
+        let tariff = tariff_at(config, interval.midpoint()).unwrap_or(config.tariffs.len());
+        let kwh = interval.on_kwh();
+        tariffs[tariff].kwh += kwh;
+        tariffs[tariff].cost += kwh * tariffs[tariff].cost_per_kwh;
+    }
+
+    let days = (timespan.until - timespan.since).num_seconds() as f64 / (60.0 * 60.0 * 24.0);
+    CostReport {
+        tariffs,
+        standing_charge: days * config.standing_charge_per_day,
+    }
+}
+
+pub fn print_report(report: &CostReport, config: &PowerConfig) {
+    let mut rows: Vec<TableTariffCost> = report
+        .tariffs
+        .iter()
+        // The default tariff is only interesting if it was used
+        .filter(|tariff| tariff.name != DEFAULT_TARIFF || tariff.kwh > 0.0)
+        .map(|tariff| TableTariffCost {
+            name: tariff.name.clone(),
+            price: format!("{:.2} {}", tariff.cost_per_kwh, config.currency),
+            kwh: format!("{:.3}", tariff.kwh),
+            cost: format!("{:.2} {}", tariff.cost, config.currency),
+        })
+        .collect();
+    if report.standing_charge > 0.0 {
+        rows.push(TableTariffCost {
+            name: "Standing charge".to_string(),
+            price: String::new(),
+            kwh: String::new(),
+            cost: format!("{:.2} {}", report.standing_charge, config.currency),
+        });
+    }
+    rows.push(TableTariffCost {
+        name: "Total".to_string(),
+        price: String::new(),
+        kwh: format!(
+            "{:.3}",
+            report.tariffs.iter().map(|tariff| tariff.kwh).sum::<f64>()
+        ),
+        cost: format!("{:.2} {}", report.total(), config.currency),
+    });
+
+    let mut table = Table::new(rows);
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tariff(start_hour: u8, end_hour: u8, days: &[TariffDay]) -> Tariff {
+        Tariff {
+            name: "Test".to_string(),
+            cost_per_kwh: 0.1,
+            start_hour,
+            end_hour,
+            days: days.to_vec(),
+        }
+    }
+
+    #[test]
+    fn boundary_hours() {
+        let day = tariff(8, 18, &[]);
+        assert!(!applies(&day, Weekday::Mon, 7));
+        assert!(applies(&day, Weekday::Mon, 8));
+        assert!(applies(&day, Weekday::Mon, 17));
+        assert!(!applies(&day, Weekday::Mon, 18));
+    }
+
+    #[test]
+    fn weekdays() {
+        let weekend = tariff(0, 24, &[TariffDay::Sat, TariffDay::Sun]);
+        assert!(!applies(&weekend, Weekday::Fri, 23));
+        assert!(applies(&weekend, Weekday::Sat, 0));
+        assert!(applies(&weekend, Weekday::Sun, 23));
+        assert!(!applies(&weekend, Weekday::Mon, 0));
+    }
+
+    #[test]
+    fn spanning_midnight() {
+        let night = tariff(22, 6, &[]);
+        assert!(!applies(&night, Weekday::Mon, 21));
+        assert!(applies(&night, Weekday::Mon, 22));
+        assert!(applies(&night, Weekday::Mon, 23));
+        assert!(applies(&night, Weekday::Tue, 0));
+        assert!(applies(&night, Weekday::Tue, 5));
+        assert!(!applies(&night, Weekday::Tue, 6));
+    }
+
+    #[test]
+    fn spanning_midnight_belongs_to_the_starting_day() {
+        let friday_night = tariff(22, 6, &[TariffDay::Fri]);
+        assert!(!applies(&friday_night, Weekday::Fri, 0));
+        assert!(!applies(&friday_night, Weekday::Fri, 5));
+        assert!(applies(&friday_night, Weekday::Fri, 22));
+        assert!(applies(&friday_night, Weekday::Sat, 0));
+        assert!(applies(&friday_night, Weekday::Sat, 5));
+        assert!(!applies(&friday_night, Weekday::Sat, 6));
+        assert!(!applies(&friday_night, Weekday::Sat, 22));
+
+        // The week wraps around as well
+        let sunday_night = tariff(23, 1, &[TariffDay::Sun]);
+        assert!(applies(&sunday_night, Weekday::Mon, 0));
+        assert!(!applies(&sunday_night, Weekday::Sun, 0));
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    io::{self, IsTerminal, Write},
+    time::{Duration, Instant},
+};
+
+use chrono::{DurationRound, Local, Timelike};
+use log::{debug, info, warn};
+use smarthome_sdk_rs::{Client, Error as SdkError};
+
+use super::{
+    draw::ParsedDevice,
+    errors::{Error, Result},
+    switch::{self, PowerJob},
+};
+use crate::{
+    cli::TimerArgs,
+    schedule::api::{self, ScheduleData, SwitchJob, TargetMode},
+};
+
+/// Server-side schedules run at a time of day with minute precision, so only delays in this range can be scheduled
+const MIN_SCHEDULE_DELAY: Duration = Duration::from_secs(60);
+const MAX_SCHEDULE_DELAY: Duration = Duration::from_secs(24 * 60 * 60);
+/// The maximum length of a schedule's name which is accepted by the server
+const MAX_SCHEDULE_NAME_LEN: usize = 30;
+
+/// Switches the devices after a delay (`--in`) or switches them now and restores their previous states later (`--for`)
+/// A server-side schedule is preferred, a local timer in the foreground is used if no schedule can be created
+pub async fn run(
+    client: &Client,
+    devices: &[ParsedDevice],
+    power_on: bool,
+    concurrency: usize,
+    verify: bool,
+    timer: &TimerArgs,
+) -> Result<()> {
+    let action = if power_on { "on" } else { "off" };
+
+    if let Some(delay) = timer.delay {
+        let jobs: Vec<SwitchJob> = devices
+            .iter()
+            .map(|device| SwitchJob {
+                switch_id: device.id.clone(),
+                power_on,
+            })
+            .collect();
+        if !timer.local && schedule(client, &format!("power {action}"), delay.0, jobs).await {
+            if verify {
+                warn!("The switches are switched by the server, so `--verify` is ignored\n => Use `--local` to switch and verify them using a local timer");
+            }
+            return Ok(());
+        }
+        if !countdown(delay.0, &format!("Switching {action} in"), "cancel").await? {
+            info!("Cancelled, no switches were changed");
+            return Ok(());
+        }
+        return switch::set_power(client, devices, power_on, concurrency, verify)
+            .await
+            .result;
+    }
+
+    // Even if some switches fail, the others still have to be restored later
+    let executed = switch::set_power(client, devices, power_on, concurrency, verify).await;
+    let switched = executed.result;
+    let Some(duration) = timer.duration else {
+        return switched;
+    };
+
+    // Only switches whose state was actually changed have to be restored
+    let restore: Vec<PowerJob> = devices
+        .iter()
+        .filter(|device| executed.switched.contains(&device.id))
+        .filter_map(|device| match &device.power {
+            Some(power) if power.status != power_on => Some(PowerJob {
+                switch_id: device.id.clone(),
+                previous: Some(power_on),
+                power_on: power.status,
+            }),
+            _ => None,
+        })
+        .collect();
+    if restore.is_empty() {
+        info!("No switch was changed, there is nothing to restore");
+        return switched;
+    }
+
+    let jobs = restore
+        .iter()
+        .map(|job| SwitchJob {
+            switch_id: job.switch_id.clone(),
+            power_on: job.power_on,
+        })
+        .collect();
+    if !timer.local && schedule(client, "power restore", duration.0, jobs).await {
+        return switched;
+    }
+    // Interrupting the countdown restores the previous states immediately
+    countdown(duration.0, "Restoring previous states in", "restore now").await?;
+    let restored = switch::execute_jobs(client, restore, concurrency, Some("power restore"))
+        .await
+        .result;
+    switched.and(restored)
+}
+
+/// Creates a server-side schedule which executes the switch jobs after the delay, rounded up to the minute
+/// Returns `false` if the delay cannot be represented by a schedule or if the schedule could not be created
+async fn schedule(client: &Client, label: &str, delay: Duration, jobs: Vec<SwitchJob>) -> bool {
+    if !(MIN_SCHEDULE_DELAY..MAX_SCHEDULE_DELAY).contains(&delay) {
+        debug!("The delay cannot be represented by a schedule, using a local timer instead");
+        return false;
+    }
+    // The time is rounded up so that the devices are never switched before the delay has elapsed
+    let now = Local::now();
+    let Some(at) = chrono::Duration::from_std(delay)
+        .ok()
+        .and_then(|delay| now.checked_add_signed(delay))
+        .and_then(|at| {
+            let truncated = at.duration_trunc(chrono::Duration::minutes(1)).ok()?;
+            match truncated == at {
+                true => Some(at),
+                false => truncated.checked_add_signed(chrono::Duration::minutes(1)),
+            }
+        })
+    else {
+        return false;
+    };
+    // Schedules run daily at their time, so a delay of almost 24 hours might run it within the current minute
+    if (at.hour(), at.minute()) == (now.hour(), now.minute()) {
+        debug!("The schedule would run within the current minute, using a local timer instead");
+        return false;
+    }
+
+    let mut name = format!(
+        "{label} {}",
+        jobs.iter()
+            .map(|job| job.switch_id.as_str())
+            .collect::<Vec<&str>>()
+            .join(", ")
+    );
+    if name.chars().count() > MAX_SCHEDULE_NAME_LEN {
+        name = name
+            .chars()
+            .take(MAX_SCHEDULE_NAME_LEN - 3)
+            .collect::<String>()
+            + "...";
+    }
+    let data = ScheduleData {
+        name,
+        hour: at.hour() as u8,
+        minute: at.minute() as u8,
+        target_mode: TargetMode::Switches,
+        homescript_code: String::new(),
+        homescript_target_id: String::new(),
+        switch_jobs: jobs,
+    };
+
+    debug!("Creating schedule `{}`...", data.name);
+    match api::create(client, &data).await {
+        Ok(()) => {
+            info!(
+                "Created schedule `{}` which runs at {} (see `schedule ls`)",
+                data.name,
+                at.format("%H:%M")
+            );
+            true
+        }
+        Err(err) => {
+            let reason = match err {
+                SdkError::Smarthome(status) => format!("the server responded with {status}"),
+                err => err.to_string(),
+            };
+            warn!("Could not create a schedule ({reason}), using a local timer instead");
+            false
+        }
+    }
+}
+
+/// Counts down in the foreground, returns `false` if the countdown was interrupted using Ctrl-C
+async fn countdown(duration: Duration, message: &str, interrupt_action: &str) -> Result<bool> {
+    let deadline = Instant::now()
+        .checked_add(duration)
+        .ok_or(Error::InvalidTimespan)?;
+    let interactive = io::stdout().is_terminal();
+    if !interactive {
+        info!("{message} {}", format_remaining(duration));
+    }
+
+    let ctrl_c = tokio::signal::ctrl_c();
+    tokio::pin!(ctrl_c);
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+    let completed = loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break true;
+        }
+        if interactive {
+            print!(
+                "\r\x1b[2K{message} \x1b[1;33m{}\x1b[1;0m (press Ctrl-C to {interrupt_action})",
+                format_remaining(remaining)
+            );
+            io::stdout().flush().map_err(Error::Terminal)?;
+        }
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = tokio::time::sleep(remaining) => {}
+            result = &mut ctrl_c => {
+                result.map_err(Error::Terminal)?;
+                break false;
+            }
+        }
+    };
+    if interactive {
+        print!("\r\x1b[2K");
+        io::stdout().flush().map_err(Error::Terminal)?;
+    }
+    Ok(completed)
+}
+
+/// Formats the remaining time as `HH:MM:SS`, rounded up to the second
+fn format_remaining(remaining: Duration) -> String {
+    let seconds = remaining.as_secs() + u64::from(remaining.subsec_nanos() > 0);
+    format!(
+        "{:02}:{:02}:{:02}",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
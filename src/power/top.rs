@@ -0,0 +1,366 @@
+use std::{io, time::Duration};
+
+use chrono::{DateTime, Local, Utc};
+use crossterm::{
+    cursor,
+    event::{Event, EventStream, KeyCode, KeyEventKind, KeyModifiers},
+    execute,
+    terminal::{self, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use futures::StreamExt;
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Sparkline, Table, TableState},
+    Frame, Terminal,
+};
+use smarthome_sdk_rs::{Client, PowerDrawPoint};
+use tokio::time::{self, MissedTickBehavior};
+
+use super::{
+    chart,
+    draw::ParsedDevice,
+    errors::{Error, Result},
+    journal::{self, Batch, Change},
+    samples::Recorder,
+    stats::{self, Timespan},
+    switch, tariff,
+};
+use crate::config::PowerConfig;
+
+const TABLE_WIDTHS: [Constraint; 5] = [
+    Constraint::Percentage(35),
+    Constraint::Percentage(25),
+    Constraint::Percentage(20),
+    Constraint::Length(6),
+    Constraint::Length(10),
+];
+/// How long a status message is displayed instead of the key help
+const STATUS_DURATION: Duration = Duration::from_secs(5);
+
+struct State {
+    /// All devices which support power, sorted by their current draw
+    devices: Vec<ParsedDevice>,
+    /// The power draw points of the last 24 hours
+    history: Vec<PowerDrawPoint>,
+    /// The used energy (in kWh) and its cost during the last 24 hours
+    usage_24_hours: Option<(f64, f64)>,
+    table: TableState,
+    /// A message about the last action or failure, displayed instead of the key help until it expires
+    status: Option<(String, time::Instant)>,
+    last_update: DateTime<Local>,
+    recorder: Recorder,
+}
+
+/// Restores the terminal once the dashboard is closed, even if an error occurred
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        Ok(Self)
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = terminal::disable_raw_mode();
+        let _ = execute!(io::stdout(), LeaveAlternateScreen, cursor::Show);
+    }
+}
+
+pub async fn start(client: &Client, config: &PowerConfig, interval: Duration) -> Result<()> {
+    // The initial fetch happens before the terminal is modified so that errors are displayed normally
+    let mut state = State {
+        devices: vec![],
+        history: vec![],
+        usage_24_hours: None,
+        table: TableState::default(),
+        status: None,
+        last_update: Local::now(),
+        recorder: Recorder::default(),
+    };
+    state.update(client, config).await?;
+
+    let _guard = TerminalGuard::enter().map_err(Error::Terminal)?;
+    let mut terminal =
+        Terminal::new(CrosstermBackend::new(io::stdout())).map_err(Error::Terminal)?;
+    let mut events = EventStream::new();
+    let mut ticker = time::interval(interval);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick completes immediately, the data has just been fetched
+    ticker.tick().await;
+
+    loop {
+        terminal
+            .draw(|frame| render(frame, &mut state, config))
+            .map_err(Error::Terminal)?;
+
+        let status_expiry = state.status.as_ref().map(|(_, expiry)| *expiry);
+        tokio::select! {
+            _ = ticker.tick() => state.refresh(client, config).await,
+            _ = time::sleep_until(status_expiry.unwrap_or_else(time::Instant::now)), if status_expiry.is_some() => {
+                state.status = None;
+            }
+            event = events.next() => match event {
+                Some(Ok(Event::Key(key))) if key.kind == KeyEventKind::Press => match key.code {
+                    KeyCode::Char('q') | KeyCode::Esc => break,
+                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => break,
+                    KeyCode::Up | KeyCode::Char('k') => state.select(-1),
+                    KeyCode::Down | KeyCode::Char('j') => state.select(1),
+                    KeyCode::Char(' ') | KeyCode::Enter => {
+                        state.toggle_selected(client).await;
+                        state.refresh(client, config).await;
+                    }
+                    KeyCode::Char('r') => state.refresh(client, config).await,
+                    _ => {}
+                },
+                // Other events, such as resizing, only require a redraw
+                Some(Ok(_)) => {}
+                Some(Err(err)) => return Err(Error::Terminal(err)),
+                None => break,
+            }
+        }
+    }
+
+    Ok(())
+}
+
+impl State {
+    async fn update(&mut self, client: &Client, config: &PowerConfig) -> Result<()> {
+        let (switches, history) = tokio::join!(client.all_switches(), client.power_usage(false));
+        let switches = switches.map_err(Error::GetDevices)?;
+        let history = history.map_err(Error::GetPowerDrawData)?;
+
+        let selected_id = self
+            .table
+            .selected()
+            .and_then(|index| self.devices.get(index))
+            .map(|device| device.id.clone());
+
+        self.devices = switches
+            .into_iter()
+            .map(ParsedDevice::from)
+            .filter(|device| device.power.is_some())
+            .collect();
+        if let Err(err) = self.recorder.record(client, &self.devices) {
+            self.set_status(format!("Could not record device states: {err}"));
+        }
+        self.devices.sort_by(|left, right| {
+            current_draw(right)
+                .cmp(&current_draw(left))
+                .then_with(|| left.name.cmp(&right.name))
+        });
+
+        self.usage_24_hours = stats::energy(&history).ok().map(|energy| {
+            (
+                energy.on_kwh,
+                tariff::report(config, &history, &Timespan::last_day()).total(),
+            )
+        });
+        self.history = history;
+        self.last_update = Local::now();
+
+        // The selection follows the device, even if its position changed
+        let selected = match selected_id {
+            Some(id) => self.devices.iter().position(|device| device.id == id),
+            None => None,
+        };
+        self.table.select(match self.devices.is_empty() {
+            true => None,
+            false => Some(selected.unwrap_or(0)),
+        });
+        Ok(())
+    }
+
+    /// Fetches new data while the dashboard is running, failures are displayed in the status line
+    async fn refresh(&mut self, client: &Client, config: &PowerConfig) {
+        if let Err(err) = self.update(client, config).await {
+            self.set_status(format!("Refresh failed: {err}"));
+        }
+    }
+
+    fn set_status(&mut self, message: String) {
+        self.status = Some((message, time::Instant::now() + STATUS_DURATION));
+    }
+
+    fn select(&mut self, offset: isize) {
+        if self.devices.is_empty() {
+            return;
+        }
+        let current = self.table.selected().unwrap_or(0) as isize;
+        let next = (current + offset).clamp(0, self.devices.len() as isize - 1);
+        self.table.select(Some(next as usize));
+    }
+
+    async fn toggle_selected(&mut self, client: &Client) {
+        let Some(device) = self
+            .table
+            .selected()
+            .and_then(|index| self.devices.get(index))
+        else {
+            return;
+        };
+        let Some(power) = &device.power else {
+            return;
+        };
+        let power_on = !power.status;
+
+        let status = match switch::set_power_helper(client, &device.id, power_on).await {
+            Ok(()) => {
+                let recorded = journal::record(Batch {
+                    time: Utc::now().timestamp_millis(),
+                    server: client.smarthome_url.to_string(),
+                    label: "power top".to_string(),
+                    changes: vec![Change {
+                        switch_id: device.id.clone(),
+                        previous: Some(power.status),
+                        new: power_on,
+                    }],
+                });
+                match recorded {
+                    Ok(()) => format!(
+                        "Turned {} `{}`",
+                        if power_on { "on" } else { "off" },
+                        device.name
+                    ),
+                    Err(err) => format!(
+                        "Turned {} `{}`, but could not record the change in the journal: {err}",
+                        if power_on { "on" } else { "off" },
+                        device.name
+                    ),
+                }
+            }
+            Err(err) => format!("Could not switch `{}`: {err}", device.name),
+        };
+        self.set_status(status);
+    }
+}
+
+/// The power which the device currently draws, devices which are turned off draw nothing
+fn current_draw(device: &ParsedDevice) -> usize {
+    match &device.power {
+        Some(power) if power.status => power.watts,
+        _ => 0,
+    }
+}
+
+fn render<B: Backend>(frame: &mut Frame<B>, state: &mut State, config: &PowerConfig) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Length(5),
+            Constraint::Min(5),
+            Constraint::Length(7),
+            Constraint::Length(1),
+        ])
+        .split(frame.size());
+
+    // Summary
+    let active: usize = state.devices.iter().map(current_draw).sum();
+    let total: usize = state
+        .devices
+        .iter()
+        .filter_map(|device| device.power.as_ref().map(|power| power.watts))
+        .sum();
+    let percent = |watts: usize| match total {
+        0 => 0.0,
+        total => watts as f64 * 100.0 / total as f64,
+    };
+    let summary = vec![
+        Spans::from(vec![
+            Span::styled("Active  ", Style::default().fg(Color::Green)),
+            Span::raw(format!("{active:>5} W ({:>3.0} %)", percent(active))),
+            Span::styled("    Passive  ", Style::default().fg(Color::Red)),
+            Span::raw(format!(
+                "{:>5} W ({:>3.0} %)",
+                total - active,
+                percent(total - active)
+            )),
+            Span::styled("    Total  ", Style::default().add_modifier(Modifier::BOLD)),
+            Span::raw(format!("{total:>5} W")),
+        ]),
+        Spans::from(match state.usage_24_hours {
+            Some((kwh, cost)) => format!(
+                "Last 24 hours: {kwh:.2} kWh ({cost:.2} {}), peak {} W",
+                config.currency,
+                state
+                    .history
+                    .iter()
+                    .map(|point| point.on.watts)
+                    .max()
+                    .unwrap_or(0)
+            ),
+            None => "Last 24 hours: not enough power draw data".to_string(),
+        }),
+        Spans::from(Span::styled(
+            format!("Updated at {}", state.last_update.format("%H:%M:%S")),
+            Style::default().fg(Color::DarkGray),
+        )),
+    ];
+    frame.render_widget(
+        Paragraph::new(summary).block(Block::default().borders(Borders::ALL).title(" Power ")),
+        chunks[0],
+    );
+
+    // Devices
+    let header = Row::new(["Name", "ID", "Room ID", "Power", "Draw"]).style(
+        Style::default()
+            .fg(Color::Green)
+            .add_modifier(Modifier::BOLD),
+    );
+    let rows = state.devices.iter().map(|device| {
+        let power = match &device.power {
+            Some(power) if power.status => Span::styled("ON", Style::default().fg(Color::Green)),
+            _ => Span::styled("OFF", Style::default().fg(Color::Red)),
+        };
+        Row::new(vec![
+            Cell::from(device.name.clone()),
+            Cell::from(device.id.clone()),
+            Cell::from(device.room_id.clone()),
+            Cell::from(power),
+            Cell::from(format!("{:>6} W", current_draw(device))),
+        ])
+    });
+    let table = Table::new(rows)
+        .header(header)
+        .block(Block::default().borders(Borders::ALL).title(" Devices "))
+        .widths(&TABLE_WIDTHS)
+        .highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .highlight_symbol("> ");
+    frame.render_stateful_widget(table, chunks[1], &mut state.table);
+
+    // History, the points are averaged into one column per cell
+    let width = chunks[2].width.saturating_sub(2).max(1) as usize;
+    let history: Vec<u64> = match (state.history.first(), state.history.last()) {
+        (Some(first), Some(last)) => chart::columns(&state.history, first.time, last.time, width)
+            .into_iter()
+            .map(|column| column.map_or(0, |(on, _)| on.round() as u64))
+            .collect(),
+        _ => vec![],
+    };
+    frame.render_widget(
+        Sparkline::default()
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Power draw (last 24 hours) "),
+            )
+            .data(&history)
+            .style(Style::default().fg(Color::Yellow)),
+        chunks[2],
+    );
+
+    // Status line
+    let footer = match &state.status {
+        Some((status, _)) => Span::raw(status.clone()),
+        None => Span::styled(
+            "q: quit  ↑/↓: select  space: toggle  r: refresh",
+            Style::default().fg(Color::DarkGray),
+        ),
+    };
+    frame.render_widget(Paragraph::new(Spans::from(footer)), chunks[3]);
+}
@@ -0,0 +1,88 @@
+use reqwest::{Method, StatusCode};
+use serde::{Deserialize, Serialize};
+use smarthome_sdk_rs::{Client, Error, Result};
+
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum TargetMode {
+    /// Executes the attached Homescript code
+    Code,
+    /// Executes an existing Homescript
+    Hms,
+    /// Changes the power state of a set of switches
+    Switches,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct Schedule {
+    pub id: u32,
+    pub data: ScheduleData,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduleData {
+    pub name: String,
+    pub hour: u8,
+    pub minute: u8,
+    pub target_mode: TargetMode,
+    pub homescript_code: String,
+    pub homescript_target_id: String,
+    pub switch_jobs: Vec<SwitchJob>,
+}
+
+#[derive(Deserialize, Serialize, Clone, Debug)]
+#[serde(rename_all = "camelCase")]
+pub struct SwitchJob {
+    pub switch_id: String,
+    pub power_on: bool,
+}
+
+#[derive(Serialize)]
+struct DeleteScheduleRequest {
+    id: u32,
+}
+
+/// Returns the pending schedules of the current user
+pub async fn list_personal(client: &Client) -> Result<Vec<Schedule>> {
+    let response = client
+        .client
+        .execute(client.build_request::<()>(Method::GET, "/api/scheduler/list/personal", None)?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(response.json::<Vec<Schedule>>().await?),
+        status => Err(Error::Smarthome(status)),
+    }
+}
+
+/// Creates a new schedule which is executed once the given time is reached
+pub async fn create(client: &Client, data: &ScheduleData) -> Result<()> {
+    let response = client
+        .client
+        .execute(client.build_request::<&ScheduleData>(
+            Method::POST,
+            "/api/scheduler/add",
+            Some(data),
+        )?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        status => Err(Error::Smarthome(status)),
+    }
+}
+
+/// Deletes a pending schedule of the current user
+pub async fn delete(client: &Client, id: u32) -> Result<()> {
+    let response = client
+        .client
+        .execute(client.build_request::<DeleteScheduleRequest>(
+            Method::DELETE,
+            "/api/scheduler/delete",
+            Some(DeleteScheduleRequest { id }),
+        )?)
+        .await?;
+    match response.status() {
+        StatusCode::OK => Ok(()),
+        status => Err(Error::Smarthome(status)),
+    }
+}
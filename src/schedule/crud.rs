@@ -0,0 +1,47 @@
+use log::{debug, info};
+use reqwest::StatusCode;
+use smarthome_sdk_rs::{Client, Error as SdkError};
+
+use super::{
+    api::{self, ScheduleData},
+    errors::{Error, Result},
+};
+
+pub async fn create_schedule(client: &Client, data: ScheduleData) -> Result<()> {
+    if data.name.is_empty() || data.name.len() > 30 {
+        return Err(Error::InvalidData(
+            "name must not be empty and shall not exceed 30 characters".to_string(),
+        ));
+    }
+    debug!(
+        "Creating schedule `{}` at {:02}:{:02}...",
+        data.name, data.hour, data.minute
+    );
+    api::create(client, &data).await.map_err(map_sdk_error)?;
+    info!("Successfully created schedule `{}`", data.name);
+    Ok(())
+}
+
+pub async fn delete_schedule(client: &Client, id: u32) -> Result<()> {
+    debug!("Deleting schedule `{id}`...");
+    match api::delete(client, id).await {
+        Ok(_) => {
+            info!("Successfully deleted schedule `{id}`");
+            Ok(())
+        }
+        Err(SdkError::Smarthome(StatusCode::UNPROCESSABLE_ENTITY)) => {
+            Err(Error::ScheduleDoesNotExist(id))
+        }
+        Err(err) => Err(map_sdk_error(err)),
+    }
+}
+
+fn map_sdk_error(err: SdkError) -> Error {
+    match err {
+        SdkError::Smarthome(StatusCode::FORBIDDEN) => Error::PermissionDenied,
+        SdkError::Smarthome(StatusCode::UNPROCESSABLE_ENTITY) => {
+            Error::InvalidData("the server rejected the schedule data".to_string())
+        }
+        _ => Error::Smarthome(err),
+    }
+}
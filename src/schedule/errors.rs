@@ -0,0 +1,36 @@
+use smarthome_sdk_rs::Error as SdkError;
+use std::fmt::Display;
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+pub enum Error {
+    FetchSchedules(SdkError),
+    ScheduleDoesNotExist(u32),
+    InvalidData(String),
+    PermissionDenied,
+    Smarthome(SdkError),
+}
+
+impl From<SdkError> for Error {
+    fn from(err: SdkError) -> Self {
+        Self::Smarthome(err)
+    }
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Self::FetchSchedules(err) => format!("Could not fetch schedules: {err}"),
+                Self::ScheduleDoesNotExist(id) =>
+                    format!("Schedule `{id}` does not exist or is inaccessible"),
+                Self::InvalidData(message) => format!("Invalid data: {message}"),
+                Self::PermissionDenied =>
+                    "You are lacking permission to manage schedules".to_string(),
+                Self::Smarthome(err) => format!("Smarthome Error: {err}"),
+            }
+        )
+    }
+}
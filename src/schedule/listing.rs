@@ -0,0 +1,71 @@
+use smarthome_sdk_rs::Client;
+use tabled::{
+    settings::{format::Format, object::Rows, Modify, Style},
+    Table, Tabled,
+};
+
+use super::{
+    api::{self, Schedule, TargetMode},
+    errors::{Error, Result},
+};
+
+#[derive(Tabled)]
+pub struct TableSchedule {
+    #[tabled(rename = "ID")]
+    pub id: u32,
+    #[tabled(rename = "Name")]
+    pub name: String,
+    #[tabled(rename = "Time")]
+    pub time: String,
+    #[tabled(rename = "Target")]
+    pub target: String,
+}
+
+impl From<Schedule> for TableSchedule {
+    fn from(source: Schedule) -> Self {
+        Self {
+            id: source.id,
+            name: source.data.name,
+            time: format!("{:02}:{:02}", source.data.hour, source.data.minute),
+            target: match source.data.target_mode {
+                TargetMode::Hms => format!("script `{}`", source.data.homescript_target_id),
+                TargetMode::Code => format!(
+                    "code ({} line(s))",
+                    source.data.homescript_code.lines().count()
+                ),
+                TargetMode::Switches => source
+                    .data
+                    .switch_jobs
+                    .iter()
+                    .map(|job| {
+                        format!(
+                            "{}: {}",
+                            job.switch_id,
+                            if job.power_on {
+                                "\x1b[1;32mON\x1b[1;0m"
+                            } else {
+                                "\x1b[1;31mOFF\x1b[1;0m"
+                            }
+                        )
+                    })
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            },
+        }
+    }
+}
+
+pub async fn list_personal(client: &Client) -> Result<()> {
+    let schedules = match api::list_personal(client).await {
+        Ok(response) => response.into_iter().map(TableSchedule::from),
+        Err(err) => return Err(Error::FetchSchedules(err)),
+    };
+    let mut table = Table::new(schedules);
+    println!(
+        "{}",
+        table.with(Style::modern().remove_horizontal()).with(
+            Modify::new(Rows::first()).with(Format::content(|s| format!("\x1b[1;32m{s}\x1b[1;0m")))
+        )
+    );
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use smarthome_sdk_rs::Client;
+
+use crate::cli::ScheduleCommand;
+use api::{ScheduleData, SwitchJob, TargetMode};
+use errors::Result;
+
+pub mod api;
+mod crud;
+mod errors;
+mod listing;
+
+pub async fn handle_subcommand(command: ScheduleCommand, client: &Client) -> Result<()> {
+    match command {
+        ScheduleCommand::Ls => listing::list_personal(client).await,
+        ScheduleCommand::New {
+            name,
+            at,
+            script,
+            code,
+            switch,
+        } => {
+            let target_mode = match (&script, &code) {
+                (Some(_), _) => TargetMode::Hms,
+                (None, Some(_)) => TargetMode::Code,
+                (None, None) => TargetMode::Switches,
+            };
+            crud::create_schedule(
+                client,
+                ScheduleData {
+                    name,
+                    hour: at.hour,
+                    minute: at.minute,
+                    target_mode,
+                    homescript_code: code.unwrap_or_default(),
+                    homescript_target_id: script.unwrap_or_default(),
+                    switch_jobs: switch
+                        .into_iter()
+                        .map(|job| SwitchJob {
+                            switch_id: job.switch_id,
+                            power_on: job.power_on,
+                        })
+                        .collect(),
+                },
+            )
+            .await
+        }
+        ScheduleCommand::Del { ids } => {
+            for id in ids {
+                crud::delete_schedule(client, id).await?
+            }
+            Ok(())
+        }
+    }
+}